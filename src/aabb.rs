@@ -0,0 +1,51 @@
+use crate::{interval, point, ray};
+
+// Axis-aligned bounding box
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb {
+    x: interval::Interval,
+    y: interval::Interval,
+    z: interval::Interval,
+}
+
+impl Aabb {
+    // Box spanning two opposite corners, given in any order
+    pub fn new(a: point::Point3, b: point::Point3) -> Aabb {
+        Aabb {
+            x: interval::Interval::new(a.x().min(b.x()), a.x().max(b.x())),
+            y: interval::Interval::new(a.y().min(b.y()), a.y().max(b.y())),
+            z: interval::Interval::new(a.z().min(b.z()), a.z().max(b.z())),
+        }
+    }
+
+    pub fn min(&self) -> point::Point3 {
+        point::Point3::new(self.x.min(), self.y.min(), self.z.min())
+    }
+
+    pub fn max(&self) -> point::Point3 {
+        point::Point3::new(self.x.max(), self.y.max(), self.z.max())
+    }
+
+    // Parametric range of the ray inside the box, clipped to ray_t (slab method)
+    pub fn hit(&self, r: &ray::Ray, ray_t: &interval::Interval) -> Option<interval::Interval> {
+        let origin = r.origin();
+        let direction = r.direction();
+        let mut t_min = ray_t.min();
+        let mut t_max = ray_t.max();
+        for (axis, o, d) in [
+            (self.x, origin.x(), direction.x()),
+            (self.y, origin.y(), direction.y()),
+            (self.z, origin.z(), direction.z()),
+        ] {
+            let inv_d = 1.0 / d;
+            let t0 = (axis.min() - o) * inv_d;
+            let t1 = (axis.max() - o) * inv_d;
+            t_min = t_min.max(t0.min(t1));
+            t_max = t_max.min(t0.max(t1));
+            if t_max <= t_min {
+                return None;
+            }
+        }
+        Some(interval::Interval::new(t_min, t_max))
+    }
+}
@@ -0,0 +1,39 @@
+use crate::{hit_record, hittable, interval, material, ray, texture, utils};
+use std::rc::Rc;
+use std::sync::Arc;
+
+// Cutout mask for foliage and fences. The first channel of the mask is the opacity at the
+// hit's (u, v); hits are kept with that probability, so partial alpha becomes stochastic
+// transparency and fully transparent texels are skipped as if the surface weren't there.
+pub struct AlphaMasked {
+    object: Rc<dyn hittable::Hittable>,
+    mask: Arc<dyn texture::Texture>,
+}
+
+impl AlphaMasked {
+    pub fn new(object: Rc<dyn hittable::Hittable>, mask: Arc<dyn texture::Texture>) -> AlphaMasked {
+        AlphaMasked { object, mask }
+    }
+}
+
+impl hittable::Hittable for AlphaMasked {
+    fn hit(&self, r: &ray::Ray, ray_t: &interval::Interval) -> Option<hit_record::HitRecord> {
+        hittable::count_hit_call();
+        let mut t_min = ray_t.min();
+        loop {
+            let rec = self
+                .object
+                .hit(r, &interval::Interval::new(t_min, ray_t.max()))?;
+            let alpha = self.mask.value(rec.u(), rec.v(), rec.p()).x();
+            if alpha >= 1.0 || utils::random_f64() < alpha {
+                return Some(rec);
+            }
+            // Transparent here, so keep looking for a later hit on the same object
+            t_min = rec.t();
+        }
+    }
+
+    fn materials(&self) -> Vec<Rc<dyn material::Material>> {
+        self.object.materials()
+    }
+}
@@ -0,0 +1,39 @@
+use crate::{color, hittable, hittable_list, integrator, interval, onb, ray, vector};
+
+// Clay preview of the geometry: the fraction of cosine-weighted directions around the first
+// hit that stay unblocked within `distance`. Materials, lights and the background are ignored.
+#[derive(Debug, Clone)]
+pub struct AmbientOcclusion {
+    distance: f64,
+    samples: u32,
+}
+
+impl AmbientOcclusion {
+    pub fn new(distance: f64, samples: u32) -> AmbientOcclusion {
+        AmbientOcclusion {
+            distance,
+            samples: samples.max(1),
+        }
+    }
+}
+
+impl integrator::Integrator for AmbientOcclusion {
+    fn radiance(&self, ray: &ray::Ray, world: &hittable_list::HittableList) -> color::Color {
+        let white = color::Color::new(1.0, 1.0, 1.0);
+        let Some(record) =
+            hittable::Hittable::hit(world, ray, &interval::Interval::new(0.001, f64::INFINITY))
+        else {
+            return white;
+        };
+        let frame = onb::Onb::new(record.normal());
+        let occlusion_t = interval::Interval::new(0.001, self.distance);
+        let open = (0..self.samples)
+            .filter(|_| {
+                let direction = frame.local(vector::random_cosine_direction());
+                let probe = ray::Ray::new(*record.p(), direction);
+                hittable::Hittable::hit(world, &probe, &occlusion_t).is_none()
+            })
+            .count();
+        white * (open as f64 / self.samples as f64)
+    }
+}
@@ -0,0 +1,100 @@
+use crate::{color, hit_record, material, microfacet, onb, ray, vector};
+
+// Anisotropic microfacet conductor for brushed metal and hair. Roughness is given
+// separately along the surface tangent and bitangent reported by the hittable.
+pub struct AnisotropicMetal {
+    albedo: color::Color,
+    alpha_tangent: f64,
+    alpha_bitangent: f64,
+}
+
+impl AnisotropicMetal {
+    pub fn new(
+        albedo: color::Color,
+        roughness_tangent: f64,
+        roughness_bitangent: f64,
+    ) -> AnisotropicMetal {
+        AnisotropicMetal {
+            albedo,
+            alpha_tangent: microfacet::roughness_to_alpha(roughness_tangent),
+            alpha_bitangent: microfacet::roughness_to_alpha(roughness_bitangent),
+        }
+    }
+
+    pub fn scatter(
+        &self,
+        r_in: &ray::Ray,
+        rec: &hit_record::HitRecord,
+        attenuation: &mut color::Color,
+        scattered: &mut ray::Ray,
+    ) -> bool {
+        let frame = onb::Onb::from_tangent(rec.normal(), rec.tangent());
+        let wo = frame.to_local(-vector::unit_vector(r_in.direction()));
+        if wo.z() <= 0.0 {
+            return false;
+        }
+        let Some((wi, m, weight)) =
+            microfacet::sample_reflection_anisotropic(&wo, self.alpha_tangent, self.alpha_bitangent)
+        else {
+            return false;
+        };
+        *attenuation = microfacet::schlick_fresnel(self.albedo, vector::dot(wo, m)) * weight;
+        *scattered = ray::Ray::new(*rec.p(), frame.local(wi));
+        true
+    }
+
+    pub fn eval(
+        &self,
+        r_in: &ray::Ray,
+        rec: &hit_record::HitRecord,
+        direction: &vector::Vec3,
+    ) -> Option<color::Color> {
+        let frame = onb::Onb::from_tangent(rec.normal(), rec.tangent());
+        let wo = frame.to_local(-vector::unit_vector(r_in.direction()));
+        let wi = frame.to_local(vector::unit_vector(*direction));
+        let half = vector::unit_vector(wo + wi);
+        let value = microfacet::eval_reflection_anisotropic(
+            &wo,
+            &wi,
+            self.alpha_tangent,
+            self.alpha_bitangent,
+        );
+        Some(microfacet::schlick_fresnel(self.albedo, vector::dot(wo, half)) * value)
+    }
+}
+
+impl material::Material for AnisotropicMetal {
+    fn scatter(
+        &self,
+        r_in: &ray::Ray,
+        rec: &hit_record::HitRecord,
+        attenuation: &mut color::Color,
+        scattered: &mut ray::Ray,
+    ) -> bool {
+        self.scatter(r_in, rec, attenuation, scattered)
+    }
+
+    fn eval(
+        &self,
+        r_in: &ray::Ray,
+        rec: &hit_record::HitRecord,
+        direction: &vector::Vec3,
+    ) -> Option<color::Color> {
+        self.eval(r_in, rec, direction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::tests::check_furnace;
+    use std::rc::Rc;
+
+    #[test]
+    fn white_furnace_along_and_across_the_grain() {
+        let material = Rc::new(AnisotropicMetal::new(color::Color::new(0.9, 0.6, 0.3), 0.5, 0.9));
+        check_furnace(material.clone(), vector::Vec3::new(0.0, 0.0, 1.0), 0.02);
+        check_furnace(material.clone(), vector::Vec3::new(1.0, 0.0, 0.7), 0.02);
+        check_furnace(material, vector::Vec3::new(0.0, 1.0, 0.7), 0.02);
+    }
+}
@@ -0,0 +1,30 @@
+use crate::{color, sky, vector};
+
+// What rays that leave the scene see
+#[derive(Debug, Clone)]
+pub enum Background {
+    // The original white-to-blue vertical gradient
+    Gradient,
+    Solid(color::Color),
+    Sky(sky::PreethamSky),
+}
+
+impl Background {
+    // `sun_sampled` says the path already gathered the sky's sun light with a shadow ray,
+    // so the sun disk is left out to avoid counting it twice
+    pub fn color(&self, direction: &vector::Vec3, sun_sampled: bool) -> color::Color {
+        match self {
+            Background::Gradient => {
+                let background_top_color = color::Color::new(0.5, 0.7, 1.0);
+                let background_bottom_color = color::Color::new(1.0, 1.0, 1.0);
+                let blend_factor_scale: f64 = 0.5;
+                let blend_factor_offset: f64 = 1.0;
+                let unit_direction = vector::unit_vector(*direction);
+                let blend_factor = blend_factor_scale * (unit_direction.y() + blend_factor_offset);
+                background_bottom_color * (1.0 - blend_factor) + background_top_color * blend_factor
+            }
+            Background::Solid(color) => *color,
+            Background::Sky(sky) => sky.radiance(direction, sun_sampled),
+        }
+    }
+}
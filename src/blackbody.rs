@@ -0,0 +1,156 @@
+// Emission of an ideal blackbody radiator, so lights can be specified by colour temperature
+use crate::{color, spectrum};
+
+// Planck's constant, speed of light and Boltzmann's constant in SI units
+const PLANCK: f64 = 6.626_070_15e-34;
+const LIGHT_SPEED: f64 = 2.997_924_58e8;
+const BOLTZMANN: f64 = 1.380_649e-23;
+
+// Wavelengths used when integrating the spectrum down to RGB
+const RGB_WAVELENGTH_SAMPLES: usize = 80;
+
+// Spectral radiance of a blackbody at `temperature` kelvin, in W / (m^2 sr nm)
+pub fn planck(lambda: f64, temperature: f64) -> f64 {
+    if temperature <= 0.0 {
+        return 0.0;
+    }
+    let lambda_m = lambda * 1e-9;
+    let exponent = PLANCK * LIGHT_SPEED / (lambda_m * BOLTZMANN * temperature);
+    let radiance =
+        2.0 * PLANCK * LIGHT_SPEED * LIGHT_SPEED / (lambda_m.powi(5) * exponent.exp_m1());
+    radiance * 1e-9
+}
+
+// How the raw Planck radiance is scaled, so brightness can be set independently of temperature
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Normalization {
+    // Luminance of one, so only the hue changes with temperature
+    Luminance,
+    // Brightest RGB channel of one
+    MaxComponent,
+    // Physical radiance; hotter bodies are far brighter
+    None,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Blackbody {
+    temperature: f64,
+    // Factor applied to the Planck radiance by the chosen normalization
+    scale: f64,
+    rgb: color::Color,
+}
+
+impl Blackbody {
+    // Blackbody at `temperature` kelvin, normalized to unit luminance
+    pub fn new(temperature: f64) -> Blackbody {
+        Blackbody::with_normalization(temperature, Normalization::Luminance)
+    }
+
+    pub fn with_normalization(temperature: f64, normalization: Normalization) -> Blackbody {
+        // Average the spectrum over the visible range the same way the spectral camera does
+        let mut xyz = color::Color::new(0.0, 0.0, 0.0);
+        let step = (spectrum::LAMBDA_MAX - spectrum::LAMBDA_MIN) / RGB_WAVELENGTH_SAMPLES as f64;
+        for i in 0..RGB_WAVELENGTH_SAMPLES {
+            let lambda = spectrum::LAMBDA_MIN + (i as f64 + 0.5) * step;
+            xyz += planck(lambda, temperature) * spectrum::cie_xyz(lambda);
+        }
+        // Very warm and very cold bodies fall slightly outside the sRGB gamut
+        let raw = spectrum::xyz_to_rgb(xyz / RGB_WAVELENGTH_SAMPLES as f64);
+        let raw = color::Color::new(raw.x().max(0.0), raw.y().max(0.0), raw.z().max(0.0));
+
+        let reference = match normalization {
+            Normalization::Luminance => 0.2126 * raw.x() + 0.7152 * raw.y() + 0.0722 * raw.z(),
+            Normalization::MaxComponent => raw.x().max(raw.y()).max(raw.z()),
+            Normalization::None => 1.0,
+        };
+        let scale = if reference > 0.0 { 1.0 / reference } else { 0.0 };
+        Blackbody {
+            temperature,
+            scale,
+            rgb: raw * scale,
+        }
+    }
+
+    pub fn temperature(&self) -> f64 {
+        self.temperature
+    }
+
+    // Linear sRGB emission, for use as the colour of lights and emissive materials
+    pub fn rgb(&self) -> color::Color {
+        self.rgb
+    }
+
+    // Normalized emission at a single wavelength, consistent with rgb() in spectral renders
+    pub fn spectral(&self, lambda: f64) -> f64 {
+        planck(lambda, self.temperature) * self.scale
+    }
+}
+
+// Light given off by an emitter: a fixed RGB colour, or a blackbody spectrum scaled by an
+// intensity, which rays carrying a wavelength see exactly
+#[derive(Debug, Clone, Copy)]
+pub enum Emission {
+    Rgb(color::Color),
+    Blackbody(Blackbody, f64),
+}
+
+impl Emission {
+    pub fn value(&self, wavelength: Option<f64>) -> color::Color {
+        match *self {
+            Emission::Rgb(emit) => emit,
+            Emission::Blackbody(blackbody, intensity) => match wavelength {
+                Some(lambda) => {
+                    let value = blackbody.spectral(lambda) * intensity;
+                    color::Color::new(value, value, value)
+                }
+                None => blackbody.rgb() * intensity,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::tests::assert_close;
+
+    fn luminance(rgb: color::Color) -> f64 {
+        0.2126 * rgb.x() + 0.7152 * rgb.y() + 0.0722 * rgb.z()
+    }
+
+    #[test]
+    fn planck_peaks_where_wiens_law_puts_it() {
+        let temperature = 5000.0;
+        let peak = (3000..8000)
+            .map(|tenths| tenths as f64 / 10.0)
+            .max_by(|a, b| planck(*a, temperature).total_cmp(&planck(*b, temperature)))
+            .unwrap();
+        assert!((peak - 2.897_771_955e6 / temperature).abs() < 0.5, "peak at {peak}");
+    }
+
+    #[test]
+    fn normalized_to_unit_luminance_and_warmer_when_cooler() {
+        for temperature in [1500.0, 3000.0, 6500.0, 12000.0] {
+            assert!((luminance(Blackbody::new(temperature).rgb()) - 1.0).abs() < 1e-9);
+        }
+        let candle = Blackbody::new(1900.0).rgb();
+        let sky = Blackbody::new(12000.0).rgb();
+        assert!(candle.x() > candle.z() && sky.z() > sky.x());
+        let max = Blackbody::with_normalization(3000.0, Normalization::MaxComponent).rgb();
+        assert!((max.x().max(max.y()).max(max.z()) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn spectral_emission_averages_to_the_rgb_colour() {
+        // What a spectral render accumulates, integrated far more finely than rgb() is
+        let blackbody = Blackbody::new(5500.0);
+        let steps = 4000;
+        let step = (spectrum::LAMBDA_MAX - spectrum::LAMBDA_MIN) / steps as f64;
+        let mut xyz = color::Color::new(0.0, 0.0, 0.0);
+        for i in 0..steps {
+            let lambda = spectrum::LAMBDA_MIN + (i as f64 + 0.5) * step;
+            xyz += blackbody.spectral(lambda) * spectrum::cie_xyz(lambda);
+        }
+        assert_close(spectrum::xyz_to_rgb(xyz / steps as f64), blackbody.rgb(), 1e-3);
+    }
+}
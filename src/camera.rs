@@ -1,12 +1,12 @@
-use crate::{color, point, vector, utils, hittable_list, hittable, interval, ray};
+use crate::{background, color, global_medium, integrator, path_tracer, point, vector, utils, hittable_list, ray, spectrum};
 use indicatif::ProgressBar;
+use std::rc::Rc;
 
 #[derive(Debug, Clone)]
 pub struct Camera {
     image_width: i32,
     image_height: i32,
     samples_per_pixel: i32,
-    max_depth: u32,
     u: vector::Vec3,
     v: vector::Vec3,
     center: point::Point3,
@@ -14,10 +14,12 @@ pub struct Camera {
     pixel_delta_u: vector::Vec3,
     pixel_delta_v: vector::Vec3,
     lens_radius: f64,
+    integrator: Rc<dyn integrator::Integrator>,
 }
 
 impl Camera {
     // Set up the camera coordinate system and image plane
+    #[allow(clippy::too_many_arguments)]
     fn new(
         image_width: i32,
         aspect_ratio: f64,
@@ -26,9 +28,9 @@ impl Camera {
         look_at: point::Point3,
         vup: vector::Vec3,
         samples_per_pixel: i32,
-        max_depth: u32,
         defocus_angle: f64,
         focus_dist: f64,
+        integrator: Rc<dyn integrator::Integrator>,
     ) -> Self {
         let image_height = (image_width as f64 / aspect_ratio) as i32;
         let focal_length = focus_dist;
@@ -57,7 +59,6 @@ impl Camera {
             image_width,
             image_height,
             samples_per_pixel,
-            max_depth,
             u,
             v,
             center,
@@ -65,50 +66,12 @@ impl Camera {
             pixel_delta_u,
             pixel_delta_v,
             lens_radius,
-        }
-    }
-
-    fn ray_color(
-        ray: &ray::Ray,
-        world: &hittable_list::HittableList,
-        remaining_depth: u32,
-    ) -> color::Color {
-        // Background gradient constants (must be local variables, not const)
-        let background_top_color = color::Color::new(0.5, 0.7, 1.0);
-        let background_bottom_color = color::Color::new(1.0, 1.0, 1.0);
-        let blend_factor_scale: f64 = 0.5;
-        let blend_factor_offset: f64 = 1.0;
-        if remaining_depth == 0 {
-            return color::Color::new(0.0, 0.0, 0.0);
-        }
-        let hit_record =
-            hittable::Hittable::hit(world, ray, &interval::Interval::new(0.001, f64::INFINITY));
-        match hit_record {
-            Some(record) => {
-                let mut scattered_ray = ray::Ray::new(
-                    point::Point3::new(0.0, 0.0, 0.0),
-                    vector::Vec3::new(0.0, 0.0, 0.0),
-                );
-                let mut attenuation = color::Color::new(0.0, 0.0, 0.0);
-                if record
-                    .material()
-                    .scatter(ray, &record, &mut attenuation, &mut scattered_ray)
-                {
-                    Camera::ray_color(&scattered_ray, world, remaining_depth - 1) * attenuation
-                } else {
-                    color::Color::new(0.0, 0.0, 0.0)
-                }
-            }
-            None => {
-                let unit_direction = vector::unit_vector(ray.direction());
-                let blend_factor = blend_factor_scale * (unit_direction.y() + blend_factor_offset);
-                background_bottom_color * (1.0 - blend_factor) + background_top_color * blend_factor
-            }
+            integrator,
         }
     }
 
     // Generate a ray through pixel (i, j), with lens sampling for depth of field
-    fn get_ray(&self, i: i32, j: i32) -> ray::Ray {
+    fn get_ray(&self, i: i32, j: i32, wavelength: f64) -> ray::Ray {
         let offset = Self::sample_square();
         let pixel_sample = self.pixel00_loc
             + ((i as f64 + offset.x()) * self.pixel_delta_u)
@@ -119,7 +82,7 @@ impl Camera {
         let offset = self.u * rd.x() + self.v * rd.y();
         let ray_origin = self.center + offset;
         let ray_direction = pixel_sample - ray_origin;
-        ray::Ray::new(ray_origin, ray_direction)
+        ray::Ray::new(ray_origin, ray_direction).with_path_wavelength(Some(wavelength))
     }
 
     fn random_in_unit_disk() -> vector::Vec3 {
@@ -150,16 +113,28 @@ impl Camera {
     ) -> Result<(), std::io::Error> {
         out.write_all(format!("P3\n{} {}\n255\n", self.image_width, self.image_height).as_bytes())?;
         let bar = ProgressBar::new(self.image_height as u64);
-        let max_depth = self.max_depth;
+        let spectral = self.integrator.spectral();
         for j in 0..self.image_height {
             bar.inc(1);
             for i in 0..self.image_width {
                 let mut pixel_color = color::Color::new(0.0, 0.0, 0.0);
                 for _ in 0..self.samples_per_pixel {
-                    let r = self.get_ray(i, j);
-                    pixel_color += Camera::ray_color(&r, world, max_depth);
+                    // Every path draws its wavelength up front, whether or not it ends up spectral
+                    let lambda = spectrum::sample_wavelength();
+                    let r = self.get_ray(i, j, lambda);
+                    if spectral {
+                        // Trace a single wavelength and accumulate its contribution in XYZ
+                        let radiance =
+                            self.integrator.radiance(&r.with_wavelength(Some(lambda)), world);
+                        pixel_color += radiance.x() * spectrum::cie_xyz(lambda);
+                    } else {
+                        pixel_color += self.integrator.radiance(&r, world);
+                    }
                 }
                 pixel_color = pixel_color / self.samples_per_pixel as f64;
+                if spectral {
+                    pixel_color = spectrum::xyz_to_rgb(pixel_color);
+                }
                 pixel_color.write_color(out)?;
             }
         }
@@ -178,8 +153,13 @@ pub struct CameraBuilder {
     vup: vector::Vec3,
     samples_per_pixel: i32,
     max_depth: u32,
+    roulette_depth: u32,
     defocus_angle: f64,
     focus_dist: f64,
+    spectral: bool,
+    medium: Option<global_medium::GlobalMedium>,
+    background: background::Background,
+    integrator: Option<Rc<dyn integrator::Integrator>>,
 }
 
 impl Default for CameraBuilder {
@@ -192,9 +172,14 @@ impl Default for CameraBuilder {
             look_at: point::Point3::new(0.0, 0.0, -1.0),
             vup: vector::Vec3::new(0.0, 1.0, 0.0),
             samples_per_pixel: 10,
-            max_depth: 10,
+            max_depth: 1000,
+            roulette_depth: 3,
             defocus_angle: 0.0,
             focus_dist: 10.0,
+            spectral: false,
+            medium: None,
+            background: background::Background::Gradient,
+            integrator: None,
         }
     }
 }
@@ -228,10 +213,19 @@ impl CameraBuilder {
         self.samples_per_pixel = spp;
         self
     }
+    // Safety limit on bounces per path. Russian roulette normally ends paths long before
+    // it, and lowering it far enough to matter cuts off light and darkens the image.
+    // The default is 1000; it was 10 before paths ended by roulette, so scenes that relied
+    // on the old default now render deeper unless they set it.
     pub fn max_depth(mut self, max_depth: u32) -> Self {
         self.max_depth = max_depth;
         self
     }
+    // Number of bounces every path makes before Russian roulette may end it
+    pub fn roulette_depth(mut self, roulette_depth: u32) -> Self {
+        self.roulette_depth = roulette_depth;
+        self
+    }
     pub fn defocus_angle(mut self, angle: f64) -> Self {
         self.defocus_angle = angle;
         self
@@ -240,7 +234,38 @@ impl CameraBuilder {
         self.focus_dist = dist;
         self
     }
+    // Trace one wavelength per path instead of RGB, for accurate dispersion and interreflection
+    pub fn spectral(mut self, spectral: bool) -> Self {
+        self.spectral = spectral;
+        self
+    }
+    // Fill the scene with fog that scatters light along every ray, including from lights
+    pub fn medium(mut self, medium: global_medium::GlobalMedium) -> Self {
+        self.medium = Some(medium);
+        self
+    }
+    // What rays leaving the scene see; register the sun light of a sky on the world separately
+    pub fn background(mut self, background: background::Background) -> Self {
+        self.background = background;
+        self
+    }
+    // Render with a different light transport algorithm. The depth, spectral, medium and
+    // background settings only configure the default path tracer and are ignored then.
+    pub fn integrator(mut self, integrator: Rc<dyn integrator::Integrator>) -> Self {
+        self.integrator = Some(integrator);
+        self
+    }
     pub fn build(self) -> Camera {
+        let integrator = self.integrator.unwrap_or_else(|| {
+            let mut path_tracer = path_tracer::PathTracer::new(self.max_depth)
+                .with_roulette_depth(self.roulette_depth)
+                .with_spectral(self.spectral)
+                .with_background(self.background);
+            if let Some(medium) = self.medium {
+                path_tracer = path_tracer.with_medium(medium);
+            }
+            Rc::new(path_tracer)
+        });
         Camera::new(
             self.image_width,
             self.aspect_ratio,
@@ -249,9 +274,9 @@ impl CameraBuilder {
             self.look_at,
             self.vup,
             self.samples_per_pixel,
-            self.max_depth,
             self.defocus_angle,
             self.focus_dist,
+            integrator,
         )
     }
 }
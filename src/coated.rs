@@ -0,0 +1,275 @@
+use crate::{color, dielectric, hit_record, material, microfacet, ray, utils, vector};
+use std::rc::Rc;
+
+// Limit on bounces between the base and the underside of the coat before the path is dropped
+const MAX_INTERNAL_BOUNCES: u32 = 16;
+
+// Smooth dielectric coating over an arbitrary base material, for car paint and lacquered wood
+pub struct Coated {
+    base: Rc<dyn material::Material>,
+    refractive_index: f64,
+    absorption: color::Color,
+    thickness: f64,
+    // Fraction of diffuse light from the base that the underside of the coat reflects back
+    // down to it, after absorption on the way up and back
+    internal_reflectance: color::Color,
+}
+
+// Cosine-weighted average of the Fresnel reflectance seen from inside a layer of the given
+// index, including total internal reflection, times the coat's transmittance up and back
+fn diffuse_internal_reflectance(
+    refractive_index: f64,
+    absorption: color::Color,
+    thickness: f64,
+) -> color::Color {
+    let steps = 256;
+    let mut sum = color::Color::new(0.0, 0.0, 0.0);
+    for i in 0..steps {
+        // cos^2 uniform in [0, 1] makes each step an equal share of cosine-weighted light
+        let cos_theta = ((i as f64 + 0.5) / steps as f64).sqrt();
+        let transmittance = dielectric::beer_lambert(absorption, thickness / cos_theta);
+        sum += transmittance
+            * transmittance
+            * microfacet::fresnel_dielectric(cos_theta, refractive_index);
+    }
+    sum / steps as f64
+}
+
+impl Coated {
+    pub fn new(base: Rc<dyn material::Material>, refractive_index: f64) -> Coated {
+        let absorption = color::Color::new(0.0, 0.0, 0.0);
+        Coated {
+            base,
+            refractive_index,
+            absorption,
+            thickness: 0.0,
+            internal_reflectance: diffuse_internal_reflectance(refractive_index, absorption, 0.0),
+        }
+    }
+
+    // Tint the coat with Beer-Lambert absorption through a layer of the given thickness
+    pub fn with_absorption(mut self, absorption: color::Color, thickness: f64) -> Coated {
+        self.absorption = absorption;
+        self.thickness = thickness;
+        self.internal_reflectance =
+            diffuse_internal_reflectance(self.refractive_index, absorption, thickness);
+        self
+    }
+
+    fn coat_transmittance(&self, direction: &vector::Vec3, normal: &vector::Vec3) -> color::Color {
+        let cosine = vector::dot(*direction, *normal).abs().max(1e-4);
+        dielectric::beer_lambert(self.absorption, self.thickness / cosine)
+    }
+
+    pub fn scatter(
+        &self,
+        r_in: &ray::Ray,
+        rec: &hit_record::HitRecord,
+        attenuation: &mut color::Color,
+        scattered: &mut ray::Ray,
+    ) -> bool {
+        // The coat is infinitely thin geometrically, so rays from inside only see the base
+        if !rec.front_face() {
+            return self.base.scatter(r_in, rec, attenuation, scattered);
+        }
+
+        let normal = *rec.normal();
+        let unit_direction = vector::unit_vector(r_in.direction());
+        let cos_theta = vector::dot(-unit_direction, normal);
+        let eta = 1.0 / self.refractive_index;
+
+        // Specular reflection off the top of the coat
+        if utils::random_f64() < microfacet::fresnel_dielectric(cos_theta, eta) {
+            *attenuation = color::Color::new(1.0, 1.0, 1.0);
+            // The mirror reflection isn't part of eval, so it must still see registered lights
+            *scattered = ray::Ray::new(*rec.p(), vector::reflect(&unit_direction, &normal))
+                .with_wavelength(r_in.wavelength())
+                .outside_eval();
+            return true;
+        }
+
+        // Random walk between the base and the coat interface until the path escapes
+        let mut direction = vector::refract(&unit_direction, &normal, eta);
+        let mut throughput = color::Color::new(1.0, 1.0, 1.0);
+        let mut covered_by_eval = true;
+        for _ in 0..MAX_INTERNAL_BOUNCES {
+            throughput = throughput * self.coat_transmittance(&direction, &normal);
+
+            let inner = ray::Ray::new(*rec.p(), direction)
+                .with_wavelength(r_in.wavelength())
+                .with_path_wavelength(r_in.path_wavelength());
+            let mut base_attenuation = color::Color::new(0.0, 0.0, 0.0);
+            let mut base_scattered = inner;
+            if !self.base.scatter(&inner, rec, &mut base_attenuation, &mut base_scattered) {
+                return false;
+            }
+            throughput = throughput * base_attenuation;
+            covered_by_eval &= base_scattered.covered_by_eval();
+            let wavelength = base_scattered.wavelength().or(r_in.wavelength());
+
+            // Light the base sends downwards leaves through the bottom of the layer
+            let up = vector::unit_vector(base_scattered.direction());
+            let cos_up = vector::dot(up, normal);
+            if cos_up <= 0.0 {
+                *attenuation = throughput;
+                *scattered = base_scattered.with_wavelength(wavelength);
+                return true;
+            }
+
+            throughput = throughput * self.coat_transmittance(&up, &normal);
+            if utils::random_f64() < microfacet::fresnel_dielectric(cos_up, self.refractive_index) {
+                // Reflected back down onto the base by the underside of the coat
+                direction = vector::reflect(&up, &-normal);
+                continue;
+            }
+
+            *attenuation = throughput;
+            *scattered = ray::Ray::new(
+                *rec.p(),
+                vector::refract(&up, &-normal, self.refractive_index),
+            )
+            .with_wavelength(wavelength);
+            if !covered_by_eval {
+                *scattered = scattered.outside_eval();
+            }
+            return true;
+        }
+        false
+    }
+
+    // Light that crosses the coat on the way in and on the way out: Fresnel transmission and
+    // coat absorption along both refracted directions, times the base's own response to the
+    // refracted directions. Light bouncing between the base and the underside of the coat is
+    // folded in as a geometric series, estimating the base's albedo as if it were diffuse.
+    pub fn eval(
+        &self,
+        r_in: &ray::Ray,
+        rec: &hit_record::HitRecord,
+        direction: &vector::Vec3,
+    ) -> Option<color::Color> {
+        if !rec.front_face() {
+            return self.base.eval(r_in, rec, direction);
+        }
+        let normal = *rec.normal();
+        let unit_direction = vector::unit_vector(r_in.direction());
+        let light = vector::unit_vector(*direction);
+        let eta = 1.0 / self.refractive_index;
+
+        // Directions under the coat: the view ray heading down and the light arriving from above
+        let inner_view = vector::refract(&unit_direction, &normal, eta);
+        let inner_light = -vector::refract(&-light, &normal, eta);
+        let inner = ray::Ray::new(*rec.p(), inner_view).with_wavelength(r_in.wavelength());
+        let base = self.base.eval(&inner, rec, &inner_light)?;
+
+        let cos_o = vector::dot(-unit_direction, normal);
+        let cos_i = vector::dot(light, normal);
+        if cos_o <= 0.0 || cos_i <= 0.0 {
+            return Some(color::Color::new(0.0, 0.0, 0.0));
+        }
+        let transmission = (1.0 - microfacet::fresnel_dielectric(cos_o, eta))
+            * (1.0 - microfacet::fresnel_dielectric(cos_i, eta));
+        // The base weights by the cosine under the coat while the light is measured outside
+        // it, and radiance leaving the denser coat spreads over a wider solid angle
+        let cos_inner = vector::dot(inner_light, normal).max(1e-4);
+        let spreading = 1.0 / (self.refractive_index * self.refractive_index);
+        let single = base
+            * self.coat_transmittance(&inner_view, &normal)
+            * self.coat_transmittance(&inner_light, &normal)
+            * (transmission * spreading * cos_i / cos_inner);
+
+        let albedo = self
+            .base
+            .eval(&inner, rec, &normal)
+            .unwrap_or(color::Color::new(0.0, 0.0, 0.0))
+            * std::f64::consts::PI;
+        let internal = self.internal_reflectance;
+        let bounces = |albedo: f64, reflectance: f64| {
+            1.0 / (1.0 - albedo.clamp(0.0, 1.0) * reflectance)
+        };
+        Some(
+            single
+                * color::Color::new(
+                    bounces(albedo.x(), internal.x()),
+                    bounces(albedo.y(), internal.y()),
+                    bounces(albedo.z(), internal.z()),
+                ),
+        )
+    }
+}
+
+impl material::Material for Coated {
+    fn scatter(
+        &self,
+        r_in: &ray::Ray,
+        rec: &hit_record::HitRecord,
+        attenuation: &mut color::Color,
+        scattered: &mut ray::Ray,
+    ) -> bool {
+        self.scatter(r_in, rec, attenuation, scattered)
+    }
+
+    fn eval(
+        &self,
+        r_in: &ray::Ray,
+        rec: &hit_record::HitRecord,
+        direction: &vector::Vec3,
+    ) -> Option<color::Color> {
+        self.eval(r_in, rec, direction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lambertian;
+    use crate::material::tests::{assert_close, surface_hit};
+
+    const SAMPLES: usize = 200_000;
+
+    // Average throughput of everything scatter sends out, of the part it leaves for light
+    // sampling, and that part integrated with eval instead
+    fn coated_furnace(material: Rc<Coated>, wo: vector::Vec3) -> [color::Color; 3] {
+        let (r_in, rec) = surface_hit(material.clone(), vector::unit_vector(wo), true);
+        let black = color::Color::new(0.0, 0.0, 0.0);
+        let mut sums = [black; 3];
+        for _ in 0..SAMPLES {
+            let mut attenuation = black;
+            let mut scattered = r_in;
+            if material.scatter(&r_in, &rec, &mut attenuation, &mut scattered) {
+                sums[0] += attenuation;
+                if scattered.covered_by_eval() {
+                    sums[1] += attenuation;
+                }
+            }
+            let wi = vector::random_unit_vector();
+            sums[2] += material.eval(&r_in, &rec, &wi).unwrap() * (4.0 * std::f64::consts::PI);
+        }
+        sums.map(|sum| sum / SAMPLES as f64)
+    }
+
+    #[test]
+    fn coated_white_base_white_furnace() {
+        let base = Rc::new(lambertian::Lambertian::new(color::Color::new(1.0, 1.0, 1.0)));
+        let material = Rc::new(Coated::new(base, 1.5));
+        for wo in [vector::Vec3::new(0.0, 0.0, 1.0), vector::Vec3::new(1.0, 0.0, 0.5)] {
+            let [total, diffuse, evaluated] = coated_furnace(material.clone(), wo);
+            // Only paths still bouncing inside the coat after the walk's limit are lost
+            assert_close(total, color::Color::new(1.0, 1.0, 1.0), 0.02);
+            assert_close(diffuse, evaluated, 0.02);
+        }
+    }
+
+    #[test]
+    fn absorbing_coat_matches_eval() {
+        let base = Rc::new(lambertian::Lambertian::new(color::Color::new(0.8, 0.5, 0.2)));
+        let material = Rc::new(
+            Coated::new(base, 1.5).with_absorption(color::Color::new(0.5, 1.0, 2.0), 0.2),
+        );
+        let [total, diffuse, evaluated] =
+            coated_furnace(material.clone(), vector::Vec3::new(1.0, 0.0, 1.0));
+        for albedo in [total.x(), total.y(), total.z()] {
+            assert!(albedo <= 1.0, "reflects {albedo}, more than it receives");
+        }
+        assert_close(diffuse, evaluated, 0.02);
+    }
+}
@@ -0,0 +1,92 @@
+use crate::{color, hit_record, hittable, interval, isotropic, material, ray, utils, vector};
+use std::rc::Rc;
+
+// Smoke or fog of constant density filling a closed boundary shape. Rays travelling
+// through it scatter after exponentially distributed distances.
+pub struct ConstantMedium {
+    boundary: Rc<dyn hittable::Hittable>,
+    neg_inv_density: f64,
+    phase_function: Rc<dyn material::Material>,
+}
+
+impl ConstantMedium {
+    pub fn new(
+        boundary: Rc<dyn hittable::Hittable>,
+        density: f64,
+        albedo: color::Color,
+    ) -> ConstantMedium {
+        Self::with_phase_function(boundary, density, Rc::new(isotropic::Isotropic::new(albedo)))
+    }
+
+    pub fn with_phase_function(
+        boundary: Rc<dyn hittable::Hittable>,
+        density: f64,
+        phase_function: Rc<dyn material::Material>,
+    ) -> ConstantMedium {
+        ConstantMedium {
+            boundary,
+            neg_inv_density: -1.0 / density,
+            phase_function,
+        }
+    }
+}
+
+impl ConstantMedium {
+    // Parametric range of the ray inside the boundary, clipped to ray_t
+    fn inside(&self, r: &ray::Ray, ray_t: &interval::Interval) -> Option<(f64, f64)> {
+        let entry = self.boundary.hit(r, &interval::Interval::universe())?;
+        let exit = self
+            .boundary
+            .hit(r, &interval::Interval::new(entry.t() + 0.0001, f64::INFINITY))?;
+
+        let t_enter = entry.t().max(ray_t.min()).max(0.0);
+        let t_exit = exit.t().min(ray_t.max());
+        (t_enter < t_exit).then_some((t_enter, t_exit))
+    }
+}
+
+impl hittable::Hittable for ConstantMedium {
+    fn hit(&self, r: &ray::Ray, ray_t: &interval::Interval) -> Option<hit_record::HitRecord> {
+        hittable::count_hit_call();
+        // Find where the ray enters and leaves the boundary, even if it starts inside
+        let (t_enter, t_exit) = self.inside(r, ray_t)?;
+
+        let ray_length = r.direction().length();
+        let distance_inside = (t_exit - t_enter) * ray_length;
+        let hit_distance = self.neg_inv_density * (1.0 - utils::random_f64()).ln();
+        if hit_distance > distance_inside {
+            return None;
+        }
+
+        // The normal and surface coordinates are arbitrary inside a volume
+        let t = t_enter + hit_distance / ray_length;
+        let normal = vector::Vec3::new(1.0, 0.0, 0.0);
+        Some(hit_record::HitRecord {
+            p: r.at(t),
+            normal,
+            geometric_normal: normal,
+            material: self.phase_function.clone(),
+            t,
+            front_face: true,
+            u: 0.0,
+            v: 0.0,
+            tangent: vector::Vec3::new(0.0, 1.0, 0.0),
+            dpdu: vector::Vec3::new(0.0, 0.0, 0.0),
+            dpdv: vector::Vec3::new(0.0, 0.0, 0.0),
+        })
+    }
+
+    fn transmittance(&self, r: &ray::Ray, ray_t: &interval::Interval) -> f64 {
+        match self.inside(r, ray_t) {
+            Some((t_enter, t_exit)) => {
+                let distance_inside = (t_exit - t_enter) * r.direction().length();
+                (distance_inside / self.neg_inv_density).exp()
+            }
+            None => 1.0,
+        }
+    }
+
+    fn materials(&self) -> Vec<Rc<dyn material::Material>> {
+        vec![self.phase_function.clone()]
+    }
+}
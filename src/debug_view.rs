@@ -0,0 +1,97 @@
+use crate::{color, hittable, hittable_list, integrator, interval, ray, vector};
+
+// What a debug render shows at the first hit of each camera ray
+#[derive(Debug, Clone, Copy)]
+pub enum DebugMode {
+    // Shading normal, facing the ray, mapped from [-1, 1] to [0, 1] per axis
+    ShadingNormal,
+    GeometricNormal,
+    // Texture coordinates in red and green
+    Uv,
+    // White up close fading to black at max_distance
+    Depth { max_distance: f64 },
+    // A random colour per material instance, chosen by the order the scene added it so it
+    // stays the same from run to run. Materials the scene doesn't list are white.
+    MaterialId,
+    // Green where rays hit the front of a surface and red where they hit the back
+    FrontFace,
+    // Heatmap of Hittable::hit calls made for the ray, nested ones included, from blue (none)
+    // to red (max_calls). Needs the hit-count feature; without it every pixel is blue.
+    HitCount { max_calls: u64 },
+}
+
+// Integrator that visualizes hit record data instead of light, for inspecting scenes.
+// Rays that miss everything are black.
+#[derive(Debug, Clone)]
+pub struct DebugView {
+    mode: DebugMode,
+}
+
+fn unit_to_color(v: &vector::Vec3) -> color::Color {
+    (vector::unit_vector(*v) + color::Color::new(1.0, 1.0, 1.0)) * 0.5
+}
+
+// Bright colour derived from an id, spread out with the splitmix64 finalizer
+fn id_to_color(id: u64) -> color::Color {
+    let mut z = id.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^= z >> 31;
+    let channel = |shift: u32| 0.2 + 0.8 * ((z >> shift) & 0xff) as f64 / 255.0;
+    color::Color::new(channel(0), channel(8), channel(16))
+}
+
+// Blue, cyan, green, yellow, red as t goes from 0 to 1
+fn heatmap(t: f64) -> color::Color {
+    let stops = [
+        color::Color::new(0.0, 0.0, 1.0),
+        color::Color::new(0.0, 1.0, 1.0),
+        color::Color::new(0.0, 1.0, 0.0),
+        color::Color::new(1.0, 1.0, 0.0),
+        color::Color::new(1.0, 0.0, 0.0),
+    ];
+    let scaled = t.clamp(0.0, 1.0) * (stops.len() - 1) as f64;
+    let i = (scaled as usize).min(stops.len() - 2);
+    let f = scaled - i as f64;
+    stops[i] * (1.0 - f) + stops[i + 1] * f
+}
+
+impl DebugView {
+    pub fn new(mode: DebugMode) -> DebugView {
+        DebugView { mode }
+    }
+}
+
+impl integrator::Integrator for DebugView {
+    fn radiance(&self, ray: &ray::Ray, world: &hittable_list::HittableList) -> color::Color {
+        hittable::reset_hit_calls();
+        let hit_record =
+            hittable::Hittable::hit(world, ray, &interval::Interval::new(0.001, f64::INFINITY));
+        let calls = hittable::hit_calls();
+        match (self.mode, hit_record) {
+            (DebugMode::HitCount { max_calls }, _) => {
+                heatmap(calls as f64 / max_calls.max(1) as f64)
+            }
+            (_, None) => color::Color::new(0.0, 0.0, 0.0),
+            (DebugMode::ShadingNormal, Some(record)) => unit_to_color(record.normal()),
+            (DebugMode::GeometricNormal, Some(record)) => unit_to_color(record.geometric_normal()),
+            (DebugMode::Uv, Some(record)) => color::Color::new(record.u(), record.v(), 0.0),
+            (DebugMode::Depth { max_distance }, Some(record)) => {
+                let distance = record.t() * ray.direction().length();
+                let brightness = (1.0 - distance / max_distance).clamp(0.0, 1.0);
+                color::Color::new(brightness, brightness, brightness)
+            }
+            (DebugMode::MaterialId, Some(record)) => match world.material_id(record.material()) {
+                Some(id) => id_to_color(id as u64),
+                None => color::Color::new(1.0, 1.0, 1.0),
+            },
+            (DebugMode::FrontFace, Some(record)) => {
+                if record.front_face() {
+                    color::Color::new(0.0, 1.0, 0.0)
+                } else {
+                    color::Color::new(1.0, 0.0, 0.0)
+                }
+            }
+        }
+    }
+}
@@ -1,13 +1,92 @@
-use crate::{color, material, ray, utils, vector};
+use crate::{color, material, ray, spectrum, thin_film, utils, vector};
+
+// Refractive index as a function of wavelength
+#[derive(Debug, Clone, Copy)]
+pub enum Dispersion {
+    Constant(f64),
+    // n = a + b / λ², with λ in micrometres
+    Cauchy { a: f64, b: f64 },
+    // n² = 1 + Σ bᵢλ² / (λ² - cᵢ), with λ in micrometres
+    Sellmeier { b: [f64; 3], c: [f64; 3] },
+}
+
+impl Dispersion {
+    // Schott N-BK7 crown glass
+    pub const BK7: Dispersion = Dispersion::Sellmeier {
+        b: [1.03961212, 0.231792344, 1.01046945],
+        c: [0.00600069867, 0.0200179144, 103.560653],
+    };
+    // Schott SF11 dense flint glass, strongly dispersive
+    pub const SF11: Dispersion = Dispersion::Sellmeier {
+        b: [1.73759695, 0.313747346, 1.89878101],
+        c: [0.013188707, 0.0623068142, 155.23629],
+    };
+
+    pub fn refractive_index(&self, wavelength: f64) -> f64 {
+        let micrometres = wavelength / 1000.0;
+        let l2 = micrometres * micrometres;
+        match *self {
+            Dispersion::Constant(n) => n,
+            Dispersion::Cauchy { a, b } => a + b / l2,
+            Dispersion::Sellmeier { b, c } => {
+                let sum: f64 = b.iter().zip(c.iter()).map(|(b, c)| b * l2 / (l2 - c)).sum();
+                (1.0 + sum).sqrt()
+            }
+        }
+    }
+
+    pub fn is_dispersive(&self) -> bool {
+        !matches!(self, Dispersion::Constant(_))
+    }
+}
 
 pub struct Dielectric {
-    refractive_index: f64,
+    dispersion: Dispersion,
+    // Beer-Lambert absorption coefficient per unit distance travelled inside the medium
+    absorption: color::Color,
+    thin_film: Option<thin_film::ThinFilm>,
 }
 
 impl Dielectric {
     pub fn new(refractive_index: f64) -> Dielectric {
         // The ratio of the refractive index of the material to the refractive index of the enclosing media
-        Dielectric { refractive_index }
+        Self::new_dispersive(Dispersion::Constant(refractive_index))
+    }
+
+    pub fn new_dispersive(dispersion: Dispersion) -> Dielectric {
+        Dielectric {
+            dispersion,
+            absorption: color::Color::new(0.0, 0.0, 0.0),
+            thin_film: None,
+        }
+    }
+
+    // Coat the surface with a film whose interference modulates the reflectance by wavelength
+    pub fn with_thin_film(mut self, film: thin_film::ThinFilm) -> Dielectric {
+        self.thin_film = Some(film);
+        self
+    }
+
+    pub fn with_absorption(mut self, absorption: color::Color) -> Dielectric {
+        self.absorption = absorption;
+        self
+    }
+
+    // Absorption chosen so that light keeps `transmittance` of its energy after travelling `distance`
+    pub fn with_transmittance(self, transmittance: color::Color, distance: f64) -> Dielectric {
+        assert!(distance > 0.0, "transmittance distance must be positive");
+        assert!(
+            [transmittance.x(), transmittance.y(), transmittance.z()]
+                .iter()
+                .all(|&t| t > 0.0 && t <= 1.0),
+            "transmittance must lie in (0, 1]"
+        );
+        let absorption = color::Color::new(
+            -transmittance.x().ln() / distance,
+            -transmittance.y().ln() / distance,
+            -transmittance.z().ln() / distance,
+        );
+        self.with_absorption(absorption)
     }
 
     pub fn scatter(
@@ -17,11 +96,32 @@ impl Dielectric {
         attenuation: &mut color::Color,
         scattered: &mut ray::Ray,
     ) -> bool {
-        *attenuation = color::Color::new(1.0, 1.0, 1.0);
+        // An RGB path switches to the wavelength its camera ray drew at the first dispersive
+        // surface, and is tinted by that wavelength's colour, which averages to white.
+        // Rays that didn't come from the camera draw their own.
+        let (wavelength, tint) = match r_in.wavelength() {
+            None if self.dispersion.is_dispersive() => {
+                let lambda = r_in
+                    .path_wavelength()
+                    .unwrap_or_else(spectrum::sample_wavelength);
+                (Some(lambda), spectrum::wavelength_to_rgb(lambda))
+            }
+            wavelength => (wavelength, color::Color::new(1.0, 1.0, 1.0)),
+        };
+        let index = self
+            .dispersion
+            .refractive_index(wavelength.unwrap_or(spectrum::LAMBDA_REFERENCE));
+
+        // A back face hit ends a segment inside the medium, which started at the previous hit
+        *attenuation = if rec.front_face() {
+            tint
+        } else {
+            tint * beer_lambert(self.absorption, rec.t() * r_in.direction().length())
+        };
         let refractive_index = if rec.front_face() {
-            1.0 / self.refractive_index
+            1.0 / index
         } else {
-            self.refractive_index
+            index
         };
 
         let unit_direction = vector::unit_vector(r_in.direction());
@@ -29,18 +129,55 @@ impl Dielectric {
         let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
         let cannot_refract = refractive_index * sin_theta > 1.0;
 
-        let direction =
-            if cannot_refract || schlick_reflectance(cos_theta, refractive_index) > utils::random_f64() {
-                vector::reflect(&unit_direction, rec.normal())
-            } else {
-                vector::refract(&unit_direction, rec.normal(), refractive_index)
-            };
+        // Both sides see the film on the outside of the object
+        let (outer, inner) = if rec.front_face() {
+            (1.0, index)
+        } else {
+            (index, 1.0)
+        };
+        let reflect = if cannot_refract {
+            true
+        } else {
+            match (self.thin_film, wavelength) {
+                (None, _) => schlick_reflectance(cos_theta, refractive_index) > utils::random_f64(),
+                (Some(film), Some(lambda)) => {
+                    film.dielectric_reflectance(cos_theta, outer, inner, lambda) > utils::random_f64()
+                }
+                (Some(film), None) => {
+                    // Choose by the mean reflectance and reweight each channel to its own share
+                    let reflectance = film.dielectric_reflectance_rgb(cos_theta, outer, inner);
+                    let mean = (reflectance.x() + reflectance.y() + reflectance.z()) / 3.0;
+                    let white = color::Color::new(1.0, 1.0, 1.0);
+                    if mean > utils::random_f64() {
+                        *attenuation = *attenuation * reflectance / mean;
+                        true
+                    } else {
+                        *attenuation = *attenuation * (white - reflectance) / (1.0 - mean);
+                        false
+                    }
+                }
+            }
+        };
+        let direction = if reflect {
+            vector::reflect(&unit_direction, rec.normal())
+        } else {
+            vector::refract(&unit_direction, rec.normal(), refractive_index)
+        };
 
-        *scattered = ray::Ray::new(*rec.p(), direction);
+        *scattered = ray::Ray::new(*rec.p(), direction).with_wavelength(wavelength);
         true
     }
 }
 
+// Fraction of light left after travelling `distance` through a medium with the given absorption
+pub fn beer_lambert(absorption: color::Color, distance: f64) -> color::Color {
+    color::Color::new(
+        (-absorption.x() * distance).exp(),
+        (-absorption.y() * distance).exp(),
+        (-absorption.z() * distance).exp(),
+    )
+}
+
 fn schlick_reflectance(cosine: f64, refraction_index: f64) -> f64 {
     let mut r0 = (1.0 - refraction_index) / (1.0 + refraction_index);
     r0 = r0 * r0;
@@ -58,3 +195,56 @@ impl material::Material for Dielectric {
         self.scatter(r_in, rec, attenuation, scattered)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::tests::surface_hit;
+    use std::rc::Rc;
+
+    #[test]
+    fn dispersion_uses_the_wavelength_of_the_camera_ray() {
+        let material = Rc::new(Dielectric::new_dispersive(Dispersion::SF11));
+        let (r_in, rec) = surface_hit(material.clone(), vector::Vec3::new(0.0, 0.0, 1.0), true);
+        let r_in = r_in.with_path_wavelength(Some(450.0));
+        let mut attenuation = color::Color::new(0.0, 0.0, 0.0);
+        let mut scattered = r_in;
+        assert!(material.scatter(&r_in, &rec, &mut attenuation, &mut scattered));
+        assert_eq!(scattered.wavelength(), Some(450.0));
+        assert_eq!(attenuation, spectrum::wavelength_to_rgb(450.0));
+    }
+
+    #[test]
+    fn light_leaving_the_medium_is_absorbed_along_the_way_inside() {
+        let material = Rc::new(
+            Dielectric::new(1.5).with_transmittance(color::Color::new(0.5, 0.25, 1.0), 2.0),
+        );
+        // The hit record sits one unit along the ray from the entry point
+        let (r_in, rec) = surface_hit(material.clone(), vector::Vec3::new(0.0, 0.0, 1.0), false);
+        let mut attenuation = color::Color::new(0.0, 0.0, 0.0);
+        let mut scattered = r_in;
+        assert!(material.scatter(&r_in, &rec, &mut attenuation, &mut scattered));
+        crate::material::tests::assert_close(
+            attenuation,
+            color::Color::new(0.5_f64.sqrt(), 0.5, 1.0),
+            1e-12,
+        );
+
+        // Entering the medium costs nothing yet
+        let (r_in, rec) = surface_hit(material.clone(), vector::Vec3::new(0.0, 0.0, 1.0), true);
+        assert!(material.scatter(&r_in, &rec, &mut attenuation, &mut scattered));
+        assert_eq!(attenuation, color::Color::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "transmittance must lie in (0, 1]")]
+    fn opaque_transmittance_is_rejected() {
+        Dielectric::new(1.5).with_transmittance(color::Color::new(0.5, 0.0, 0.5), 1.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "transmittance distance must be positive")]
+    fn zero_transmittance_distance_is_rejected() {
+        Dielectric::new(1.5).with_transmittance(color::Color::new(0.5, 0.5, 0.5), 0.0);
+    }
+}
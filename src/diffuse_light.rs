@@ -0,0 +1,58 @@
+use crate::{blackbody, color, hit_record, material, ray};
+
+// Surface that glows uniformly in every direction from its front face and scatters nothing
+pub struct DiffuseLight {
+    emission: blackbody::Emission,
+    registered: bool,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: color::Color) -> DiffuseLight {
+        DiffuseLight {
+            emission: blackbody::Emission::Rgb(emit),
+            registered: false,
+        }
+    }
+
+    // Glow with the colour of a blackbody; rays carrying a wavelength see its exact spectrum
+    pub fn blackbody(blackbody: blackbody::Blackbody, intensity: f64) -> DiffuseLight {
+        DiffuseLight {
+            emission: blackbody::Emission::Blackbody(blackbody, intensity),
+            registered: false,
+        }
+    }
+
+    // Mark the emitter as also added to the world as a light, such as a SphereLight of
+    // the same shape, so its light is only gathered through shadow rays
+    pub fn registered_as_light(mut self) -> DiffuseLight {
+        self.registered = true;
+        self
+    }
+
+    pub fn emitted(&self, r_in: &ray::Ray, rec: &hit_record::HitRecord) -> color::Color {
+        if !rec.front_face() {
+            return color::Color::new(0.0, 0.0, 0.0);
+        }
+        self.emission.value(r_in.wavelength())
+    }
+}
+
+impl material::Material for DiffuseLight {
+    fn scatter(
+        &self,
+        _r_in: &ray::Ray,
+        _rec: &hit_record::HitRecord,
+        _attenuation: &mut color::Color,
+        _scattered: &mut ray::Ray,
+    ) -> bool {
+        false
+    }
+
+    fn emitted(&self, r_in: &ray::Ray, rec: &hit_record::HitRecord) -> color::Color {
+        self.emitted(r_in, rec)
+    }
+
+    fn is_registered_light(&self) -> bool {
+        self.registered
+    }
+}
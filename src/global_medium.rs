@@ -0,0 +1,86 @@
+use crate::{aabb, color, heterogeneous_medium, interval, onb, ray, utils, vector};
+
+// Homogeneous fog filling the whole scene, or an optional box of it. Unbounded fog
+// also swallows light from distant sources, so bound it for light shafts from the sun.
+#[derive(Debug, Clone)]
+pub struct GlobalMedium {
+    density: f64,
+    albedo: color::Color,
+    // Henyey-Greenstein anisotropy of the phase function
+    g: f64,
+    bounds: Option<aabb::Aabb>,
+}
+
+impl GlobalMedium {
+    pub fn new(density: f64, albedo: color::Color) -> GlobalMedium {
+        GlobalMedium {
+            density,
+            albedo,
+            g: 0.0,
+            bounds: None,
+        }
+    }
+
+    pub fn with_anisotropy(mut self, g: f64) -> GlobalMedium {
+        self.g = g;
+        self
+    }
+
+    pub fn with_bounds(mut self, bounds: aabb::Aabb) -> GlobalMedium {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    pub fn albedo(&self) -> color::Color {
+        self.albedo
+    }
+
+    // Part of the ray's parametric range that lies inside the fog
+    fn overlap(&self, r: &ray::Ray, ray_t: &interval::Interval) -> Option<interval::Interval> {
+        match &self.bounds {
+            Some(bounds) => bounds.hit(r, ray_t),
+            None => Some(*ray_t),
+        }
+    }
+
+    // Sample where the ray first scatters in the fog, if before the end of ray_t
+    pub fn sample_scattering(&self, r: &ray::Ray, ray_t: &interval::Interval) -> Option<f64> {
+        let range = self.overlap(r, ray_t)?;
+        let free_flight = -(1.0 - utils::random_f64()).ln() / self.density;
+        let t = range.min() + free_flight / r.direction().length();
+        (t < range.max()).then_some(t)
+    }
+
+    // Fraction of light that crosses the fog along ray_t
+    pub fn transmittance(&self, r: &ray::Ray, ray_t: &interval::Interval) -> f64 {
+        match self.overlap(r, ray_t) {
+            Some(range) if range.size() > 0.0 => {
+                let distance = range.size() * r.direction().length();
+                if distance.is_infinite() {
+                    0.0
+                } else {
+                    (-self.density * distance).exp()
+                }
+            }
+            _ => 1.0,
+        }
+    }
+
+    // Phase function value between the ray's travel direction and a new direction
+    pub fn phase(&self, incoming: &vector::Vec3, outgoing: &vector::Vec3) -> f64 {
+        let cos_theta = vector::dot(vector::unit_vector(*incoming), vector::unit_vector(*outgoing));
+        heterogeneous_medium::henyey_greenstein(cos_theta, self.g)
+    }
+
+    // Sample a new direction from the phase function; its weight is exactly one
+    pub fn sample_direction(&self, incoming: &vector::Vec3) -> vector::Vec3 {
+        let cos_theta = heterogeneous_medium::sample_henyey_greenstein(self.g);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * std::f64::consts::PI * utils::random_f64();
+        onb::Onb::new(incoming).local(vector::Vec3::new(
+            sin_theta * phi.cos(),
+            sin_theta * phi.sin(),
+            cos_theta,
+        ))
+    }
+}
@@ -0,0 +1,202 @@
+use crate::{
+    color, hit_record, hittable, interval, material, onb, ray, utils, vector, voxel_grid,
+};
+use std::f64::consts::PI;
+use std::rc::Rc;
+use std::sync::Arc;
+
+// Henyey-Greenstein phase function value for the cosine between the incoming
+// propagation direction and the scattered direction
+pub fn henyey_greenstein(cos_theta: f64, g: f64) -> f64 {
+    let denom = 1.0 + g * g - 2.0 * g * cos_theta;
+    (1.0 - g * g) / (4.0 * PI * denom * denom.sqrt())
+}
+
+// Sample the cosine of the scattering angle from the Henyey-Greenstein distribution
+pub fn sample_henyey_greenstein(g: f64) -> f64 {
+    let xi = utils::random_f64();
+    if g.abs() < 1e-3 {
+        return 1.0 - 2.0 * xi;
+    }
+    let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * xi);
+    ((1.0 + g * g - s * s) / (2.0 * g)).clamp(-1.0, 1.0)
+}
+
+// What happens at a real collision inside the volume: scattering with probability albedo,
+// and for fire, emission weighted by the absorbed fraction
+struct VolumeCollision {
+    albedo: color::Color,
+    g: f64,
+    emission: Option<(Arc<voxel_grid::VoxelGrid>, color::Color)>,
+}
+
+impl material::Material for VolumeCollision {
+    fn scatter(
+        &self,
+        r_in: &ray::Ray,
+        rec: &hit_record::HitRecord,
+        attenuation: &mut color::Color,
+        scattered: &mut ray::Ray,
+    ) -> bool {
+        let cos_theta = sample_henyey_greenstein(self.g);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * utils::random_f64();
+        let frame = onb::Onb::new(&r_in.direction());
+        let direction = frame.local(vector::Vec3::new(
+            sin_theta * phi.cos(),
+            sin_theta * phi.sin(),
+            cos_theta,
+        ));
+        *scattered = ray::Ray::new(*rec.p(), direction);
+        *attenuation = self.albedo;
+        true
+    }
+
+    fn eval(
+        &self,
+        r_in: &ray::Ray,
+        _rec: &hit_record::HitRecord,
+        direction: &vector::Vec3,
+    ) -> Option<color::Color> {
+        let cos_theta = vector::dot(
+            vector::unit_vector(r_in.direction()),
+            vector::unit_vector(*direction),
+        );
+        Some(self.albedo * henyey_greenstein(cos_theta, self.g))
+    }
+
+    fn emitted(&self, _r_in: &ray::Ray, rec: &hit_record::HitRecord) -> color::Color {
+        match &self.emission {
+            Some((grid, emission)) => {
+                (color::Color::new(1.0, 1.0, 1.0) - self.albedo) * *emission * grid.value(rec.p())
+            }
+            None => color::Color::new(0.0, 0.0, 0.0),
+        }
+    }
+}
+
+// Spatially varying volume (clouds, smoke, fire) backed by a voxel density grid.
+// Collisions are found with delta tracking and shadow rays use ratio tracking.
+pub struct HeterogeneousMedium {
+    density: Arc<voxel_grid::VoxelGrid>,
+    density_scale: f64,
+    // Upper bound on the extinction anywhere in the grid, used for tentative collisions
+    majorant: f64,
+    albedo: color::Color,
+    g: f64,
+    collision: Rc<VolumeCollision>,
+}
+
+impl HeterogeneousMedium {
+    // g in (-1, 1) is the Henyey-Greenstein anisotropy: negative scatters backwards,
+    // positive forwards, zero is isotropic
+    pub fn new(
+        density: Arc<voxel_grid::VoxelGrid>,
+        density_scale: f64,
+        albedo: color::Color,
+        g: f64,
+    ) -> HeterogeneousMedium {
+        let majorant = density.max_value() * density_scale;
+        HeterogeneousMedium {
+            density,
+            density_scale,
+            majorant,
+            albedo,
+            g,
+            collision: Rc::new(VolumeCollision {
+                albedo,
+                g,
+                emission: None,
+            }),
+        }
+    }
+
+    // Emission of `color` scaled by a second grid, e.g. temperature for fire. Light is only
+    // emitted where the density grid also has matter.
+    pub fn with_emission(
+        mut self,
+        emission: Arc<voxel_grid::VoxelGrid>,
+        color: color::Color,
+    ) -> HeterogeneousMedium {
+        self.collision = Rc::new(VolumeCollision {
+            albedo: self.albedo,
+            g: self.g,
+            emission: Some((emission, color)),
+        });
+        self
+    }
+
+    fn extinction(&self, p: &vector::Vec3) -> f64 {
+        self.density.value(p) * self.density_scale
+    }
+
+    // Step to the next tentative collision along the ray, in units of t
+    fn free_flight(&self, ray_length: f64) -> f64 {
+        -(1.0 - utils::random_f64()).ln() / (self.majorant * ray_length)
+    }
+}
+
+impl hittable::Hittable for HeterogeneousMedium {
+    fn hit(&self, r: &ray::Ray, ray_t: &interval::Interval) -> Option<hit_record::HitRecord> {
+        hittable::count_hit_call();
+        if self.majorant <= 0.0 {
+            return None;
+        }
+        let range = self.density.bounds().hit(r, ray_t)?;
+        let ray_length = r.direction().length();
+
+        // Delta tracking: accept tentative collisions with probability extinction / majorant
+        let mut t = range.min().max(0.0);
+        loop {
+            t += self.free_flight(ray_length);
+            if t >= range.max() {
+                return None;
+            }
+            let p = r.at(t);
+            if utils::random_f64() * self.majorant < self.extinction(&p) {
+                let normal = vector::Vec3::new(1.0, 0.0, 0.0);
+                return Some(hit_record::HitRecord {
+                    p,
+                    normal,
+                    geometric_normal: normal,
+                    material: self.collision.clone(),
+                    t,
+                    front_face: true,
+                    u: 0.0,
+                    v: 0.0,
+                    tangent: vector::Vec3::new(0.0, 1.0, 0.0),
+                    dpdu: vector::Vec3::new(0.0, 0.0, 0.0),
+                    dpdv: vector::Vec3::new(0.0, 0.0, 0.0),
+                });
+            }
+        }
+    }
+
+    fn transmittance(&self, r: &ray::Ray, ray_t: &interval::Interval) -> f64 {
+        if self.majorant <= 0.0 {
+            return 1.0;
+        }
+        let Some(range) = self.density.bounds().hit(r, ray_t) else {
+            return 1.0;
+        };
+        let ray_length = r.direction().length();
+
+        // Ratio tracking: weight by the null-collision probability at every tentative collision
+        let mut transmittance = 1.0;
+        let mut t = range.min().max(0.0);
+        loop {
+            t += self.free_flight(ray_length);
+            if t >= range.max() {
+                return transmittance;
+            }
+            transmittance *= 1.0 - self.extinction(&r.at(t)) / self.majorant;
+            if transmittance <= 0.0 {
+                return 0.0;
+            }
+        }
+    }
+
+    fn materials(&self) -> Vec<Rc<dyn material::Material>> {
+        vec![self.collision.clone() as Rc<dyn material::Material>]
+    }
+}
@@ -4,11 +4,22 @@ use std::rc::Rc;
 pub struct HitRecord {
     /// Point of intersection between the ray and the surface
     pub p: point::Point3,
+    /// Shading normal, facing against the ray. Normal and bump maps perturb this one.
     pub normal: vector::Vec3,
+    /// Normal of the actual geometry, facing the same side as `normal`
+    pub geometric_normal: vector::Vec3,
     pub material: Rc<dyn material::Material>,
     /// Distance along the ray where the hit occurs (origin + t * direction)
     pub t: f64,
     pub front_face: bool,
+    /// Surface coordinates of the hit, used for texture lookups
+    pub u: f64,
+    pub v: f64,
+    /// Unit surface tangent along increasing u, for anisotropic materials
+    pub tangent: vector::Vec3,
+    /// How far the hit point moves per unit of u and of v, zero where there is no (u, v)
+    pub dpdu: vector::Vec3,
+    pub dpdv: vector::Vec3,
 }
 
 impl HitRecord {
@@ -18,6 +29,9 @@ impl HitRecord {
     pub fn normal(&self) -> &vector::Vec3 {
         &self.normal
     }
+    pub fn geometric_normal(&self) -> &vector::Vec3 {
+        &self.geometric_normal
+    }
     pub fn material(&self) -> &Rc<dyn material::Material> {
         &self.material
     }
@@ -27,6 +41,36 @@ impl HitRecord {
     pub fn front_face(&self) -> bool {
         self.front_face
     }
+    pub fn u(&self) -> f64 {
+        self.u
+    }
+    pub fn v(&self) -> f64 {
+        self.v
+    }
+    pub fn tangent(&self) -> &vector::Vec3 {
+        &self.tangent
+    }
+    pub fn dpdu(&self) -> &vector::Vec3 {
+        &self.dpdu
+    }
+    pub fn dpdv(&self) -> &vector::Vec3 {
+        &self.dpdv
+    }
+    // Completes the tangent frame along increasing v. It is built from the outward side of
+    // the surface, so it points the same way whichever side the ray hit.
+    pub fn bitangent(&self) -> vector::Vec3 {
+        let bitangent = vector::cross(self.normal, self.tangent);
+        if self.front_face {
+            bitangent
+        } else {
+            -bitangent
+        }
+    }
+    // True when `direction` is on different sides of the shading and geometric surfaces.
+    // Following such a ray would leak light through the surface.
+    pub fn shading_mismatch(&self, direction: &vector::Vec3) -> bool {
+        vector::dot(*direction, self.normal) * vector::dot(*direction, self.geometric_normal) < 0.0
+    }
 }
 
 pub fn face_normal(r: &ray::Ray, outward_normal: &vector::Vec3) -> (bool, vector::Vec3) {
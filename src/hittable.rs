@@ -1,5 +1,50 @@
-use crate::{hit_record, interval, ray};
+use crate::{hit_record, interval, material, ray};
+#[cfg(feature = "hit-count")]
+use std::cell::Cell;
+use std::rc::Rc;
+
+#[cfg(feature = "hit-count")]
+thread_local! {
+    // Hittable::hit calls made on this thread, for the hit-count debug view
+    static HIT_CALLS: Cell<u64> = const { Cell::new(0) };
+}
+
+// Every Hittable::hit implementation calls this first, so calls made by wrappers, media and
+// nested lists are counted too. Without the hit-count feature it does nothing, keeping the
+// cost out of normal renders.
+#[inline(always)]
+pub fn count_hit_call() {
+    #[cfg(feature = "hit-count")]
+    HIT_CALLS.with(|calls| calls.set(calls.get() + 1));
+}
+
+// Always zero unless the crate is built with the hit-count feature
+pub fn hit_calls() -> u64 {
+    #[cfg(feature = "hit-count")]
+    return HIT_CALLS.with(Cell::get);
+    #[cfg(not(feature = "hit-count"))]
+    0
+}
+
+pub fn reset_hit_calls() {
+    #[cfg(feature = "hit-count")]
+    HIT_CALLS.with(|calls| calls.set(0));
+}
 
 pub trait Hittable {
     fn hit(&self, r: &ray::Ray, ray_t: &interval::Interval) -> Option<hit_record::HitRecord>;
+
+    // Fraction of light that makes it along the ray through this object, for shadow rays.
+    // Surfaces block completely; participating media override this with a smooth estimate.
+    fn transmittance(&self, r: &ray::Ray, ray_t: &interval::Interval) -> f64 {
+        match self.hit(r, ray_t) {
+            Some(_) => 0.0,
+            None => 1.0,
+        }
+    }
+
+    // Materials that hit records from this object can carry, in a fixed order
+    fn materials(&self) -> Vec<Rc<dyn material::Material>> {
+        Vec::new()
+    }
 }
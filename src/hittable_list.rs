@@ -1,17 +1,57 @@
-use crate::{hit_record, hittable, interval, ray};
+use crate::{hit_record, hittable, interval, light, light_bvh, material, ray};
+use std::cell::OnceCell;
+use std::collections::HashMap;
 use std::rc::Rc;
 
 pub struct HittableList {
     objects: Vec<Rc<dyn hittable::Hittable>>,
+    lights: Vec<Rc<dyn light::Light>>,
+    // Built on first use and dropped whenever a light is added
+    light_bvh: OnceCell<light_bvh::LightBvh>,
+    // Each material's index in the order objects were added, keyed by the shared instance
+    material_ids: HashMap<*const (), usize>,
 }
 
 impl HittableList {
     pub fn new(objects: Vec<Rc<dyn hittable::Hittable>>) -> Self {
-        Self { objects }
+        let mut list = Self {
+            objects: Vec::new(),
+            lights: Vec::new(),
+            light_bvh: OnceCell::new(),
+            material_ids: HashMap::new(),
+        };
+        for object in objects {
+            list.add(object);
+        }
+        list
     }
     pub fn add(&mut self, object: Rc<dyn hittable::Hittable>) {
+        for material in object.materials() {
+            let next = self.material_ids.len();
+            self.material_ids.entry(material_key(&material)).or_insert(next);
+        }
         self.objects.push(object);
     }
+    // Stable index of a material added with the scene's objects, the same from run to run
+    pub fn material_id(&self, material: &Rc<dyn material::Material>) -> Option<usize> {
+        self.material_ids.get(&material_key(material)).copied()
+    }
+    // Lights are sampled with shadow rays; they are not intersected by camera rays
+    pub fn add_light(&mut self, light: Rc<dyn light::Light>) {
+        self.lights.push(light);
+        self.light_bvh = OnceCell::new();
+    }
+    pub fn lights(&self) -> &[Rc<dyn light::Light>] {
+        &self.lights
+    }
+    pub fn light_bvh(&self) -> &light_bvh::LightBvh {
+        self.light_bvh.get_or_init(|| light_bvh::LightBvh::new(&self.lights))
+    }
+}
+
+// Identity of a shared material instance; only used for lookups, never shown
+fn material_key(material: &Rc<dyn material::Material>) -> *const () {
+    Rc::as_ptr(material) as *const ()
 }
 
 impl Default for HittableList {
@@ -22,6 +62,7 @@ impl Default for HittableList {
 
 impl hittable::Hittable for HittableList {
     fn hit(&self, r: &ray::Ray, ray_t: &interval::Interval) -> Option<hit_record::HitRecord> {
+        hittable::count_hit_call();
         let mut closest_so_far = ray_t.max();
         let mut hit_anything = None;
         for object in &self.objects {
@@ -33,4 +74,42 @@ impl hittable::Hittable for HittableList {
         }
         hit_anything
     }
+
+    fn transmittance(&self, r: &ray::Ray, ray_t: &interval::Interval) -> f64 {
+        let mut transmittance = 1.0;
+        for object in &self.objects {
+            transmittance *= object.transmittance(r, ray_t);
+            if transmittance == 0.0 {
+                break;
+            }
+        }
+        transmittance
+    }
+
+    fn materials(&self) -> Vec<Rc<dyn material::Material>> {
+        self.objects.iter().flat_map(|object| object.materials()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color, lambertian, point, sphere};
+
+    #[test]
+    fn material_ids_follow_the_order_materials_were_added() {
+        let sphere = |material: &Rc<dyn material::Material>| -> Rc<dyn hittable::Hittable> {
+            Rc::new(sphere::Sphere::new(point::Point3::new(0.0, 0.0, 0.0), 1.0, material.clone()))
+        };
+        let grey = color::Color::new(0.5, 0.5, 0.5);
+        let first: Rc<dyn material::Material> = Rc::new(lambertian::Lambertian::new(grey));
+        let second: Rc<dyn material::Material> = Rc::new(lambertian::Lambertian::new(grey));
+        let unlisted: Rc<dyn material::Material> = Rc::new(lambertian::Lambertian::new(grey));
+
+        let nested = HittableList::new(vec![sphere(&first), sphere(&second)]);
+        let world = HittableList::new(vec![sphere(&second), Rc::new(nested), sphere(&first)]);
+        assert_eq!(world.material_id(&second), Some(0));
+        assert_eq!(world.material_id(&first), Some(1));
+        assert_eq!(world.material_id(&unlisted), None);
+    }
 }
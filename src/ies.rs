@@ -0,0 +1,428 @@
+// IES LM-63 photometric profiles, the measured angular intensity of real light fixtures.
+// Only type C photometry is supported, which covers nearly every architectural fixture:
+// vertical angles run from 0 (straight down the fixture's axis) to 180, and horizontal
+// angles turn around the axis.
+use std::io;
+
+#[derive(Debug, Clone)]
+pub struct IesProfile {
+    vertical_angles: Vec<f64>,
+    horizontal_angles: Vec<f64>,
+    // Candela for every vertical angle, one row per horizontal angle, with the
+    // file's multiplier and ballast factor already applied
+    candela: Vec<Vec<f64>>,
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+// The numbers following the TILT line, read in order
+struct Tokens {
+    numbers: Vec<f64>,
+    position: usize,
+}
+
+impl Tokens {
+    fn remaining(&self) -> usize {
+        self.numbers.len() - self.position
+    }
+
+    fn next(&mut self) -> io::Result<f64> {
+        let value = *self
+            .numbers
+            .get(self.position)
+            .ok_or_else(|| invalid("truncated IES profile"))?;
+        self.position += 1;
+        Ok(value)
+    }
+
+    // A count of values that follow, which can't be more than the file still holds
+    fn count(&mut self) -> io::Result<usize> {
+        let value = self.next()?;
+        if value < 0.0 || value.fract() != 0.0 {
+            return Err(invalid("malformed count in IES profile"));
+        }
+        if value > self.remaining() as f64 {
+            return Err(invalid("truncated IES profile"));
+        }
+        Ok(value as usize)
+    }
+
+    fn skip(&mut self, count: usize) -> io::Result<()> {
+        self.list(count).map(|_| ())
+    }
+
+    fn list(&mut self, count: usize) -> io::Result<Vec<f64>> {
+        if count > self.remaining() {
+            return Err(invalid("truncated IES profile"));
+        }
+        let values = self.numbers[self.position..self.position + count].to_vec();
+        self.position += count;
+        Ok(values)
+    }
+}
+
+// Index of the interval containing x in an ascending list, and the blend factor within it
+fn bracket(angles: &[f64], x: f64) -> (usize, f64) {
+    let upper = angles
+        .partition_point(|&angle| angle <= x)
+        .clamp(1, angles.len() - 1);
+    let lower = upper - 1;
+    let span = angles[upper] - angles[lower];
+    let t = if span > 0.0 {
+        (x - angles[lower]) / span
+    } else {
+        0.0
+    };
+    (lower, t.clamp(0.0, 1.0))
+}
+
+impl IesProfile {
+    pub fn load(path: &str) -> io::Result<IesProfile> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn parse(text: &str) -> io::Result<IesProfile> {
+        // Everything up to the TILT line is the version line and [KEYWORD] metadata
+        let mut lines = text.lines();
+        let tilt = loop {
+            let line = lines
+                .next()
+                .ok_or_else(|| invalid("missing TILT line in IES profile"))?;
+            if let Some(tilt) = line.trim().strip_prefix("TILT=") {
+                break tilt.trim().to_string();
+            }
+        };
+
+        // The rest is free-form numbers separated by whitespace and sometimes commas
+        let rest: Vec<&str> = lines.collect();
+        let numbers = rest
+            .iter()
+            .flat_map(|line| line.split(|c: char| c.is_whitespace() || c == ','))
+            .filter(|token| !token.is_empty())
+            .map(|token| match token.parse::<f64>() {
+                Ok(value) if value.is_finite() => Ok(value),
+                _ => Err(invalid("malformed number in IES profile")),
+            })
+            .collect::<io::Result<Vec<f64>>>()?;
+        let mut tokens = Tokens {
+            numbers,
+            position: 0,
+        };
+
+        match tilt.as_str() {
+            "NONE" => {}
+            // Lamp tilt data only matters for fixtures aimed away from their rated position,
+            // so it is read past and ignored
+            "INCLUDE" => {
+                tokens.next()?;
+                let pairs = tokens.count()?;
+                let values = pairs
+                    .checked_mul(2)
+                    .ok_or_else(|| invalid("truncated IES profile"))?;
+                tokens.skip(values)?;
+            }
+            _ => return Err(invalid("IES tilt files are not supported")),
+        }
+
+        let _lamp_count = tokens.next()?;
+        let _lumens_per_lamp = tokens.next()?;
+        let multiplier = tokens.next()?;
+        let vertical_count = tokens.count()?;
+        let horizontal_count = tokens.count()?;
+        let photometric_type = tokens.next()?;
+        // Units and luminous opening dimensions don't affect a point emitter
+        tokens.skip(4)?;
+        let ballast_factor = tokens.next()?;
+        let _future_use = tokens.next()?;
+        let _input_watts = tokens.next()?;
+
+        if photometric_type != 1.0 {
+            return Err(invalid("only type C IES photometry is supported"));
+        }
+        if vertical_count < 2 || horizontal_count < 1 {
+            return Err(invalid("IES profile needs at least two vertical angles"));
+        }
+        // Both angle lists and the candela table must fit in what is left of the file
+        let needed = vertical_count
+            .checked_mul(horizontal_count)
+            .and_then(|table| table.checked_add(vertical_count))
+            .and_then(|total| total.checked_add(horizontal_count));
+        if needed.is_none_or(|needed| needed > tokens.remaining()) {
+            return Err(invalid("truncated IES profile"));
+        }
+
+        let vertical_angles = tokens.list(vertical_count)?;
+        let horizontal_angles = tokens.list(horizontal_count)?;
+        let scale = multiplier * ballast_factor;
+        let candela = (0..horizontal_count)
+            .map(|_| {
+                tokens
+                    .list(vertical_count)
+                    .map(|row| row.into_iter().map(|value| value * scale).collect())
+            })
+            .collect::<io::Result<Vec<Vec<f64>>>>()?;
+
+        let ascending = |angles: &[f64]| angles.windows(2).all(|pair| pair[0] < pair[1]);
+        if !ascending(&vertical_angles) || !ascending(&horizontal_angles) {
+            return Err(invalid("IES angles must be in increasing order"));
+        }
+
+        Ok(IesProfile {
+            vertical_angles,
+            horizontal_angles,
+            candela,
+        })
+    }
+
+    // Fold a horizontal angle into the range covered by the file, using its symmetry
+    fn fold_horizontal(&self, horizontal: f64) -> f64 {
+        let first = self.horizontal_angles[0];
+        let last = self.horizontal_angles[self.horizontal_angles.len() - 1];
+        let phi = horizontal.rem_euclid(360.0);
+        if first == 0.0 && last == 90.0 {
+            // Symmetric in each quadrant
+            let phi = if phi > 180.0 { 360.0 - phi } else { phi };
+            if phi > 90.0 {
+                180.0 - phi
+            } else {
+                phi
+            }
+        } else if first == 0.0 && last == 180.0 {
+            // Symmetric about the 0-180 degree plane
+            if phi > 180.0 {
+                360.0 - phi
+            } else {
+                phi
+            }
+        } else if first == 90.0 && last == 270.0 {
+            // Symmetric about the 90-270 degree plane
+            if phi < 90.0 {
+                180.0 - phi
+            } else if phi > 270.0 {
+                540.0 - phi
+            } else {
+                phi
+            }
+        } else {
+            phi
+        }
+    }
+
+    fn candela_in_plane(&self, row: usize, vertical: f64) -> f64 {
+        let (i, t) = bracket(&self.vertical_angles, vertical);
+        let values = &self.candela[row];
+        values[i] * (1.0 - t) + values[i + 1] * t
+    }
+
+    // Luminous intensity in candela at the given angles in degrees. Directions outside
+    // the measured vertical range give no light.
+    pub fn candela(&self, vertical: f64, horizontal: f64) -> f64 {
+        let first = self.vertical_angles[0];
+        let last = self.vertical_angles[self.vertical_angles.len() - 1];
+        if vertical < first || vertical > last {
+            return 0.0;
+        }
+        // Rotationally symmetric fixtures store a single plane
+        if self.horizontal_angles.len() == 1 {
+            return self.candela_in_plane(0, vertical);
+        }
+
+        let phi = self.fold_horizontal(horizontal);
+        let angles = &self.horizontal_angles;
+        let count = angles.len();
+        // A full 360 degree file may stop short of 360; wrap back to the first plane
+        let (lower, upper, t) = if phi > angles[count - 1] {
+            let span = angles[0] + 360.0 - angles[count - 1];
+            (count - 1, 0, (phi - angles[count - 1]) / span)
+        } else if phi < angles[0] {
+            (0, 0, 0.0)
+        } else {
+            let (i, t) = bracket(angles, phi);
+            (i, i + 1, t)
+        };
+        self.candela_in_plane(lower, vertical) * (1.0 - t)
+            + self.candela_in_plane(upper, vertical) * t
+    }
+
+    pub fn max_candela(&self) -> f64 {
+        self.candela
+            .iter()
+            .flatten()
+            .fold(0.0, |max: f64, &value| max.max(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SYMMETRIC: &str = "IESNA:LM-63-2002
+[TEST] symmetric downlight
+[MANUFAC] Example
+TILT=NONE
+1 1000 1 3 1 1 1 0.1 0.1 0
+1 1 20
+0 45 90
+0
+1000 500 0
+";
+
+    const QUADRANT: &str = "IESNA:LM-63-2002
+TILT=NONE
+1 -1 2 2 2 1 1 0 0 0
+1.0 1 50
+0 90
+0 90
+100 50
+200 100
+";
+
+    const BILATERAL_WITH_TILT: &str = "IESNA91
+[TEST] bilateral, tilt data and comma separators
+TILT=INCLUDE
+1
+2
+0, 90
+1, 0.5
+1 1000 1 2 3 1 1 0 0 0
+0.5 1 50
+0 180
+0 90 180
+100, 100
+200, 200
+300, 300
+";
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn rotationally_symmetric_profile() {
+        let profile = IesProfile::parse(SYMMETRIC).unwrap();
+        assert_close(profile.candela(0.0, 0.0), 1000.0);
+        assert_close(profile.candela(45.0, 123.0), 500.0);
+        assert_close(profile.candela(22.5, 300.0), 750.0);
+        assert_close(profile.candela(90.0, 0.0), 0.0);
+        // Nothing is measured above the horizon
+        assert_close(profile.candela(135.0, 0.0), 0.0);
+        assert_close(profile.max_candela(), 1000.0);
+    }
+
+    #[test]
+    fn quadrant_symmetry_mirrors_into_first_quadrant() {
+        let profile = IesProfile::parse(QUADRANT).unwrap();
+        // Multiplier of 2 applies to every value
+        assert_close(profile.candela(0.0, 0.0), 200.0);
+        assert_close(profile.candela(0.0, 90.0), 400.0);
+        assert_close(profile.candela(0.0, 45.0), 300.0);
+        assert_close(profile.candela(0.0, 135.0), profile.candela(0.0, 45.0));
+        assert_close(profile.candela(0.0, 270.0), 400.0);
+        assert_close(profile.candela(45.0, 315.0), profile.candela(45.0, 45.0));
+    }
+
+    #[test]
+    fn bilateral_symmetry_and_tilt_are_handled() {
+        let profile = IesProfile::parse(BILATERAL_WITH_TILT).unwrap();
+        // Ballast factor of 0.5 halves every value
+        assert_close(profile.candela(0.0, 0.0), 50.0);
+        assert_close(profile.candela(90.0, 90.0), 100.0);
+        assert_close(profile.candela(0.0, 270.0), 100.0);
+        assert_close(profile.candela(0.0, 225.0), profile.candela(0.0, 135.0));
+    }
+
+    #[test]
+    fn full_profile_wraps_around() {
+        let text = "TILT=NONE
+1 1000 1 2 4 1 1 0 0 0
+1 1 10
+0 90
+0 90 180 270
+100 100
+200 200
+300 300
+400 400
+";
+        let profile = IesProfile::parse(text).unwrap();
+        assert_close(profile.candela(0.0, 180.0), 300.0);
+        assert_close(profile.candela(0.0, 315.0), 250.0);
+        assert_close(profile.candela(0.0, -45.0), 250.0);
+    }
+
+    #[test]
+    fn malformed_profiles_are_rejected() {
+        let invalid_data =
+            |text: &str| IesProfile::parse(text).unwrap_err().kind() == io::ErrorKind::InvalidData;
+        assert!(invalid_data("IESNA:LM-63-2002\n[TEST] no tilt\n"));
+        assert!(invalid_data(
+            "TILT=NONE\n1 1000 1 3 1 1 1 0 0 0\n1 1 20\n0 45 90\n0\n1000 500\n"
+        ));
+        assert!(invalid_data(
+            "TILT=NONE\n1 1000 1 2 1 2 1 0 0 0\n1 1 20\n0 90\n0\n10 5\n"
+        ));
+        assert!(invalid_data("TILT=lamp.tlt\n"));
+        assert!(invalid_data("TILT=NONE\n1 1000 x 2 1 1 1 0 0 0\n"));
+    }
+
+    #[test]
+    fn impossible_counts_are_rejected() {
+        let invalid_data =
+            |text: &str| IesProfile::parse(text).unwrap_err().kind() == io::ErrorKind::InvalidData;
+        let body = "1 1000 1 2 1 1 1 0 0 0\n1 1 20\n0 90\n0\n10 5\n";
+        // Tilt pair counts that are huge, negative, fractional or longer than the file
+        for count in ["1e30", "-1", "1.5", "40"] {
+            assert!(invalid_data(&format!("TILT=INCLUDE\n1\n{count}\n0 1\n{body}")));
+        }
+        // Angle counts that are huge, negative, fractional or longer than the file
+        for (vertical, horizontal) in [("1e30", "1"), ("2", "1e30"), ("-2", "1"), ("2.5", "1")] {
+            assert!(invalid_data(&format!(
+                "TILT=NONE\n1 1000 1 {vertical} {horizontal} 1 1 0 0 0\n1 1 20\n0 90\n0\n10 5\n"
+            )));
+        }
+        // Counts that fit individually but whose candela table runs past the end
+        assert!(invalid_data(
+            "TILT=NONE\n1 1000 1 3 3 1 1 0 0 0\n1 1 20\n0 45 90\n0 45 90\n1 2 3\n"
+        ));
+    }
+
+    #[test]
+    fn non_finite_values_are_rejected() {
+        let invalid_data =
+            |text: &str| IesProfile::parse(text).unwrap_err().kind() == io::ErrorKind::InvalidData;
+        for value in ["NaN", "inf", "-inf", "1e400"] {
+            // As an angle, as a candela value and as the multiplier
+            assert!(invalid_data(&format!(
+                "TILT=NONE\n1 1000 1 2 1 1 1 0 0 0\n1 1 20\n0 {value}\n0\n10 5\n"
+            )));
+            assert!(invalid_data(&format!(
+                "TILT=NONE\n1 1000 1 2 1 1 1 0 0 0\n1 1 20\n0 90\n0\n10 {value}\n"
+            )));
+            assert!(invalid_data(&format!(
+                "TILT=NONE\n1 1000 {value} 2 1 1 1 0 0 0\n1 1 20\n0 90\n0\n10 5\n"
+            )));
+        }
+    }
+
+    #[test]
+    fn manufacturer_sample_file() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/recessed_downlight.ies");
+        let profile = IesProfile::load(path).unwrap();
+        assert_close(profile.candela(0.0, 0.0), 1260.0);
+        assert_close(profile.candela(2.5, 0.0), 1256.4);
+        assert_close(profile.candela(30.0, 45.0), 967.1);
+        assert_close(profile.candela(30.0, 33.75), 0.5 * (1001.3 + 967.1));
+        // One quadrant is stored; the others mirror it
+        assert_close(profile.candela(30.0, 135.0), 967.1);
+        assert_close(profile.candela(30.0, 270.0), 918.8);
+        assert_close(profile.candela(60.0, 200.0), profile.candela(60.0, 20.0));
+        // Nothing is measured above the ceiling
+        assert_close(profile.candela(90.0, 0.0), 0.0);
+        assert_close(profile.candela(120.0, 0.0), 0.0);
+        assert_close(profile.max_candela(), 1260.0);
+    }
+}
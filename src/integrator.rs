@@ -0,0 +1,15 @@
+use crate::{color, hittable_list, ray};
+
+// Light transport algorithm the camera renders with. The camera generates the rays and
+// the integrator decides what each one sees, so alternative algorithms such as ambient
+// occlusion or debug views plug in without changing the camera.
+pub trait Integrator: std::fmt::Debug {
+    // Radiance arriving at the camera along `ray`
+    fn radiance(&self, ray: &ray::Ray, world: &hittable_list::HittableList) -> color::Color;
+
+    // Whether the camera should give each ray a sampled wavelength and read the result
+    // as a single spectral value
+    fn spectral(&self) -> bool {
+        false
+    }
+}
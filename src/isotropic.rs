@@ -0,0 +1,54 @@
+use crate::{color, hit_record, material, ray, vector};
+
+// Phase function of a participating medium that scatters equally in every direction
+pub struct Isotropic {
+    albedo: color::Color,
+}
+
+impl Isotropic {
+    pub fn new(albedo: color::Color) -> Isotropic {
+        Isotropic { albedo }
+    }
+
+    pub fn scatter(
+        &self,
+        _r_in: &ray::Ray,
+        rec: &hit_record::HitRecord,
+        attenuation: &mut color::Color,
+        scattered: &mut ray::Ray,
+    ) -> bool {
+        *scattered = ray::Ray::new(*rec.p(), vector::random_unit_vector());
+        *attenuation = self.albedo;
+        true
+    }
+
+    pub fn eval(
+        &self,
+        _r_in: &ray::Ray,
+        _rec: &hit_record::HitRecord,
+        _direction: &vector::Vec3,
+    ) -> Option<color::Color> {
+        Some(self.albedo / (4.0 * std::f64::consts::PI))
+    }
+}
+
+impl material::Material for Isotropic {
+    fn scatter(
+        &self,
+        r_in: &ray::Ray,
+        rec: &hit_record::HitRecord,
+        attenuation: &mut color::Color,
+        scattered: &mut ray::Ray,
+    ) -> bool {
+        self.scatter(r_in, rec, attenuation, scattered)
+    }
+
+    fn eval(
+        &self,
+        r_in: &ray::Ray,
+        rec: &hit_record::HitRecord,
+        direction: &vector::Vec3,
+    ) -> Option<color::Color> {
+        self.eval(r_in, rec, direction)
+    }
+}
@@ -11,12 +11,13 @@ impl Lambertian {
 
     pub fn scatter(
         &self,
-        ray: &ray::Ray,
+        _ray: &ray::Ray,
         hit_record: &hit_record::HitRecord,
         attenuation: &mut color::Color,
         scattered: &mut ray::Ray,
     ) -> bool {
-        let mut scatter_direction = *hit_record.normal() + vector::random_in_unit_sphere();
+        // A point on the unit sphere tangent to the surface gives a cosine-weighted direction
+        let mut scatter_direction = *hit_record.normal() + vector::random_unit_vector();
         if scatter_direction.near_zero() {
             scatter_direction = *hit_record.normal();
         }
@@ -24,6 +25,16 @@ impl Lambertian {
         *attenuation = self.albedo;
         true
     }
+
+    pub fn eval(
+        &self,
+        _r_in: &ray::Ray,
+        hit_record: &hit_record::HitRecord,
+        direction: &vector::Vec3,
+    ) -> Option<color::Color> {
+        let cosine = vector::dot(vector::unit_vector(*direction), *hit_record.normal()).max(0.0);
+        Some(self.albedo * (cosine / std::f64::consts::PI))
+    }
 }
 
 impl material::Material for Lambertian {
@@ -36,4 +47,37 @@ impl material::Material for Lambertian {
     ) -> bool {
         self.scatter(r_in, rec, attenuation, scattered)
     }
+
+    fn eval(
+        &self,
+        r_in: &ray::Ray,
+        rec: &hit_record::HitRecord,
+        direction: &vector::Vec3,
+    ) -> Option<color::Color> {
+        self.eval(r_in, rec, direction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::tests::{check_furnace, scatter_and_eval, surface_hit};
+    use std::rc::Rc;
+
+    #[test]
+    fn white_furnace() {
+        let material = Rc::new(Lambertian::new(color::Color::new(1.0, 0.5, 0.25)));
+        check_furnace(material.clone(), vector::Vec3::new(0.0, 0.0, 1.0), 0.02);
+        check_furnace(material, vector::Vec3::new(1.0, 0.0, 0.2), 0.02);
+    }
+
+    #[test]
+    fn scatters_with_cosine_weighting() {
+        let material = Rc::new(Lambertian::new(color::Color::new(1.0, 1.0, 1.0)));
+        let (r_in, rec) = surface_hit(material, vector::Vec3::new(0.0, 0.0, 1.0), true);
+        // The mean cosine of a cosine-weighted lobe is 2/3
+        let (scattered, evaluated) = scatter_and_eval(&r_in, &rec, |wi| wi.z());
+        assert!((scattered.x() - 2.0 / 3.0).abs() < 0.01, "mean cosine {}", scattered.x());
+        assert!((evaluated.x() - 2.0 / 3.0).abs() < 0.02, "mean cosine {}", evaluated.x());
+    }
 }
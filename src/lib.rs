@@ -1,16 +1,48 @@
+pub mod aabb;
+pub mod alpha_mask;
+pub mod ambient_occlusion;
+pub mod anisotropic_metal;
+pub mod background;
+pub mod blackbody;
 pub mod camera;
+pub mod coated;
 pub mod color;
+pub mod constant_medium;
+pub mod debug_view;
 pub mod dielectric;
+pub mod diffuse_light;
+pub mod global_medium;
+pub mod heterogeneous_medium;
 pub mod hit_record;
 pub mod hittable;
 pub mod hittable_list;
+pub mod ies;
+pub mod integrator;
 pub mod interval;
+pub mod isotropic;
 pub mod lambertian;
+pub mod light;
+pub mod light_bvh;
 pub mod material;
 pub mod metal;
+pub mod microfacet;
+pub mod mix;
+pub mod normal_map;
+pub mod onb;
+pub mod oren_nayar;
+pub mod path_tracer;
 pub mod point;
+pub mod principled;
+pub mod quad;
 pub mod random_scene;
 pub mod ray;
+pub mod rough_dielectric;
+pub mod sky;
+pub mod spectrum;
 pub mod sphere;
+pub mod subsurface;
+pub mod texture;
+pub mod thin_film;
 pub mod utils;
 pub mod vector;
+pub mod voxel_grid;
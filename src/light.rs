@@ -0,0 +1,351 @@
+use crate::{aabb, blackbody, color, ies, onb, point, utils, vector};
+use std::f64::consts::PI;
+
+// One sample of the light arriving at a point, used to trace a shadow ray
+pub struct LightSample {
+    // Unit vector from the shaded point towards the light
+    pub direction: vector::Vec3,
+    // Distance to the sampled point on the light; infinite for distant lights
+    pub distance: f64,
+    // Incident radiance divided by the probability of picking this sample
+    pub radiance: color::Color,
+}
+
+// Light sources that can be sampled explicitly with shadow rays. Register them on the
+// world with HittableList::add_light.
+pub trait Light {
+    // `wavelength` is the one carried by the path in spectral renders, for spectral emitters
+    fn sample(&self, p: &point::Point3, wavelength: Option<f64>) -> Option<LightSample>;
+
+    // Rough estimate of the total emitted power, used to choose between many lights
+    fn power(&self) -> f64;
+
+    // Box around everything that emits, or None for lights at infinity
+    fn bounds(&self) -> Option<aabb::Aabb>;
+}
+
+// Scalar brightness of a colour, for comparing lights
+fn luminance(color: color::Color) -> f64 {
+    0.2126 * color.x() + 0.7152 * color.y() + 0.0722 * color.z()
+}
+
+// Uniform direction within the cone of half-angle acos(cos_max) around `axis`
+fn sample_cone(axis: &vector::Vec3, cos_max: f64) -> vector::Vec3 {
+    let cos_theta = 1.0 + utils::random_f64() * (cos_max - 1.0);
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * PI * utils::random_f64();
+    let local = vector::Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+    onb::Onb::new(axis).local(local)
+}
+
+// Light emitted from a single point in every direction, falling off with the inverse
+// square of distance. An optional range fades it smoothly to zero at that distance.
+pub struct PointLight {
+    position: point::Point3,
+    intensity: color::Color,
+    range: Option<f64>,
+}
+
+impl PointLight {
+    pub fn new(position: point::Point3, intensity: color::Color) -> PointLight {
+        PointLight {
+            position,
+            intensity,
+            range: None,
+        }
+    }
+
+    pub fn with_range(mut self, range: f64) -> PointLight {
+        self.range = Some(range);
+        self
+    }
+}
+
+// Intensity scale for a light at `distance`, including any range window
+fn falloff(distance: f64, range: Option<f64>) -> f64 {
+    let inverse_square = 1.0 / (distance * distance);
+    match range {
+        Some(range) => {
+            let ratio = (distance / range).powi(4);
+            let window = (1.0 - ratio).max(0.0);
+            inverse_square * window * window
+        }
+        None => inverse_square,
+    }
+}
+
+impl Light for PointLight {
+    fn sample(&self, p: &point::Point3, _wavelength: Option<f64>) -> Option<LightSample> {
+        let to_light = self.position - *p;
+        let distance = to_light.length();
+        if distance <= 0.0 {
+            return None;
+        }
+        Some(LightSample {
+            direction: to_light / distance,
+            distance,
+            radiance: self.intensity * falloff(distance, self.range),
+        })
+    }
+
+    fn power(&self) -> f64 {
+        4.0 * PI * luminance(self.intensity)
+    }
+
+    fn bounds(&self) -> Option<aabb::Aabb> {
+        Some(aabb::Aabb::new(self.position, self.position))
+    }
+}
+
+// Point light restricted to a cone, fading between the inner and outer cone angles
+pub struct SpotLight {
+    position: point::Point3,
+    direction: vector::Vec3,
+    intensity: color::Color,
+    cos_inner: f64,
+    cos_outer: f64,
+    range: Option<f64>,
+}
+
+impl SpotLight {
+    // Cone angles are half-angles from the spot's axis, in degrees
+    pub fn new(
+        position: point::Point3,
+        direction: vector::Vec3,
+        intensity: color::Color,
+        inner_angle: f64,
+        outer_angle: f64,
+    ) -> SpotLight {
+        SpotLight {
+            position,
+            direction: vector::unit_vector(direction),
+            intensity,
+            cos_inner: utils::degrees_to_radians(inner_angle).cos(),
+            cos_outer: utils::degrees_to_radians(outer_angle.max(inner_angle)).cos(),
+            range: None,
+        }
+    }
+
+    pub fn with_range(mut self, range: f64) -> SpotLight {
+        self.range = Some(range);
+        self
+    }
+
+    // Smoothstep from the outer cone (0) to the inner cone (1)
+    fn cone(&self, cos_theta: f64) -> f64 {
+        if cos_theta >= self.cos_inner {
+            return 1.0;
+        }
+        if cos_theta <= self.cos_outer {
+            return 0.0;
+        }
+        let t = (cos_theta - self.cos_outer) / (self.cos_inner - self.cos_outer);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl Light for SpotLight {
+    fn sample(&self, p: &point::Point3, _wavelength: Option<f64>) -> Option<LightSample> {
+        let to_light = self.position - *p;
+        let distance = to_light.length();
+        if distance <= 0.0 {
+            return None;
+        }
+        let direction = to_light / distance;
+        let cone = self.cone(vector::dot(-direction, self.direction));
+        if cone <= 0.0 {
+            return None;
+        }
+        Some(LightSample {
+            direction,
+            distance,
+            radiance: self.intensity * (cone * falloff(distance, self.range)),
+        })
+    }
+
+    fn power(&self) -> f64 {
+        // Solid angle of a cone halfway between the inner and outer angles
+        let cos_mid = 0.5 * (self.cos_inner + self.cos_outer);
+        2.0 * PI * (1.0 - cos_mid) * luminance(self.intensity)
+    }
+
+    fn bounds(&self) -> Option<aabb::Aabb> {
+        Some(aabb::Aabb::new(self.position, self.position))
+    }
+}
+
+// Point light shaped by a measured IES profile. The profile's candela values are
+// multiplied by `intensity`, so a white intensity of one reproduces the fixture exactly.
+// The fixture hangs straight down with horizontal angle 0 along +x until reoriented.
+pub struct IesLight {
+    position: point::Point3,
+    profile: ies::IesProfile,
+    intensity: color::Color,
+    frame: onb::Onb,
+    range: Option<f64>,
+}
+
+impl IesLight {
+    pub fn new(position: point::Point3, profile: ies::IesProfile, intensity: color::Color) -> IesLight {
+        IesLight {
+            position,
+            profile,
+            intensity,
+            frame: onb::Onb::from_tangent(
+                &vector::Vec3::new(0.0, -1.0, 0.0),
+                &vector::Vec3::new(1.0, 0.0, 0.0),
+            ),
+            range: None,
+        }
+    }
+
+    // Aim the fixture's axis (vertical angle 0) along `axis`, with horizontal angle 0
+    // towards `reference`
+    pub fn with_orientation(mut self, axis: vector::Vec3, reference: vector::Vec3) -> IesLight {
+        self.frame = onb::Onb::from_tangent(&axis, &reference);
+        self
+    }
+
+    pub fn with_range(mut self, range: f64) -> IesLight {
+        self.range = Some(range);
+        self
+    }
+}
+
+impl Light for IesLight {
+    fn sample(&self, p: &point::Point3, _wavelength: Option<f64>) -> Option<LightSample> {
+        let to_light = self.position - *p;
+        let distance = to_light.length();
+        if distance <= 0.0 {
+            return None;
+        }
+        let direction = to_light / distance;
+        // Angles of the direction the light leaves in, in the fixture's frame
+        let local = self.frame.to_local(-direction);
+        let vertical = local.z().clamp(-1.0, 1.0).acos().to_degrees();
+        let horizontal = local.y().atan2(local.x()).to_degrees();
+        let candela = self.profile.candela(vertical, horizontal);
+        if candela <= 0.0 {
+            return None;
+        }
+        Some(LightSample {
+            direction,
+            distance,
+            radiance: self.intensity * (candela * falloff(distance, self.range)),
+        })
+    }
+
+    fn power(&self) -> f64 {
+        // Upper bound, as if the peak intensity went out in every direction
+        4.0 * PI * self.profile.max_candela() * luminance(self.intensity)
+    }
+
+    fn bounds(&self) -> Option<aabb::Aabb> {
+        Some(aabb::Aabb::new(self.position, self.position))
+    }
+}
+
+// Distant light such as the sun. `direction` is the way the light travels and
+// `irradiance` is measured on a surface facing it. A non-zero angular diameter (in
+// degrees) samples a disk of directions, which softens shadows.
+pub struct DirectionalLight {
+    direction: vector::Vec3,
+    irradiance: color::Color,
+    cos_max: f64,
+}
+
+impl DirectionalLight {
+    pub fn new(
+        direction: vector::Vec3,
+        irradiance: color::Color,
+        angular_diameter: f64,
+    ) -> DirectionalLight {
+        DirectionalLight {
+            direction: vector::unit_vector(direction),
+            irradiance,
+            cos_max: utils::degrees_to_radians(angular_diameter / 2.0).cos(),
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample(&self, _p: &point::Point3, _wavelength: Option<f64>) -> Option<LightSample> {
+        Some(LightSample {
+            direction: sample_cone(&-self.direction, self.cos_max),
+            distance: f64::INFINITY,
+            radiance: self.irradiance,
+        })
+    }
+
+    fn power(&self) -> f64 {
+        luminance(self.irradiance)
+    }
+
+    fn bounds(&self) -> Option<aabb::Aabb> {
+        None
+    }
+}
+
+// Spherical area light with uniform radiance, such as a glowing ball. Pair it with a
+// sphere of the same size whose material is a DiffuseLight registered as a light, so
+// camera rays see it too.
+pub struct SphereLight {
+    center: point::Point3,
+    radius: f64,
+    emission: blackbody::Emission,
+}
+
+impl SphereLight {
+    pub fn new(center: point::Point3, radius: f64, radiance: color::Color) -> SphereLight {
+        SphereLight {
+            center,
+            radius,
+            emission: blackbody::Emission::Rgb(radiance),
+        }
+    }
+
+    // Glow like DiffuseLight::blackbody with the same arguments, down to the exact spectrum
+    pub fn blackbody(
+        center: point::Point3,
+        radius: f64,
+        blackbody: blackbody::Blackbody,
+        intensity: f64,
+    ) -> SphereLight {
+        SphereLight {
+            center,
+            radius,
+            emission: blackbody::Emission::Blackbody(blackbody, intensity),
+        }
+    }
+}
+
+impl Light for SphereLight {
+    fn sample(&self, p: &point::Point3, wavelength: Option<f64>) -> Option<LightSample> {
+        let to_center = self.center - *p;
+        let distance_squared = to_center.length_squared();
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared {
+            return None;
+        }
+        // Sample the cone of directions the sphere subtends, whose pdf is one over its solid angle
+        let cos_max = (1.0 - radius_squared / distance_squared).max(0.0).sqrt();
+        let direction = sample_cone(&to_center, cos_max);
+        let along = vector::dot(direction, to_center);
+        let distance = along - (radius_squared - (distance_squared - along * along)).max(0.0).sqrt();
+        Some(LightSample {
+            direction,
+            distance,
+            radiance: self.emission.value(wavelength) * (2.0 * PI * (1.0 - cos_max)),
+        })
+    }
+
+    fn power(&self) -> f64 {
+        // Radiance times pi gives the exitance, over the whole surface area
+        luminance(self.emission.value(None)) * PI * 4.0 * PI * self.radius * self.radius
+    }
+
+    fn bounds(&self) -> Option<aabb::Aabb> {
+        let extent = vector::Vec3::new(self.radius, self.radius, self.radius);
+        Some(aabb::Aabb::new(self.center - extent, self.center + extent))
+    }
+}
@@ -0,0 +1,202 @@
+// Hierarchy over the scene's lights for choosing one light per shading point. Each step
+// down the tree picks a child in proportion to its power over its squared distance, so
+// nearby bright lights are sampled often and the noise hardly grows with the light count.
+// Lights at infinity have no position to cluster and are kept aside to be sampled every time.
+use crate::{aabb, light, point, utils};
+use std::rc::Rc;
+
+enum NodeKind {
+    Leaf(usize),
+    Interior(Box<Node>, Box<Node>),
+}
+
+struct Node {
+    bounds: aabb::Aabb,
+    power: f64,
+    kind: NodeKind,
+}
+
+impl Node {
+    // Estimated contribution of everything under this node at p
+    fn importance(&self, p: &point::Point3) -> f64 {
+        let min = self.bounds.min();
+        let max = self.bounds.max();
+        let center = (min + max) * 0.5;
+        // Inside or near a large cluster, distance to its center says little; don't let
+        // the estimate grow past what the cluster's own size allows
+        let half_diagonal_squared = 0.25 * (max - min).length_squared();
+        let distance_squared = (*p - center)
+            .length_squared()
+            .max(half_diagonal_squared)
+            .max(1e-8);
+        self.power / distance_squared
+    }
+}
+
+fn surrounding_box(a: &aabb::Aabb, b: &aabb::Aabb) -> aabb::Aabb {
+    let (a_min, a_max, b_min, b_max) = (a.min(), a.max(), b.min(), b.max());
+    aabb::Aabb::new(
+        point::Point3::new(
+            a_min.x().min(b_min.x()),
+            a_min.y().min(b_min.y()),
+            a_min.z().min(b_min.z()),
+        ),
+        point::Point3::new(
+            a_max.x().max(b_max.x()),
+            a_max.y().max(b_max.y()),
+            a_max.z().max(b_max.z()),
+        ),
+    )
+}
+
+// Split the lights at the median of their centers along the widest axis
+fn build(lights: &[Rc<dyn light::Light>], mut entries: Vec<(usize, aabb::Aabb)>) -> Node {
+    if entries.len() == 1 {
+        let (index, bounds) = entries[0];
+        return Node {
+            bounds,
+            power: lights[index].power().max(0.0),
+            kind: NodeKind::Leaf(index),
+        };
+    }
+
+    let center = |bounds: &aabb::Aabb| (bounds.min() + bounds.max()) * 0.5;
+    let first = center(&entries[0].1);
+    let centers = entries.iter().fold(aabb::Aabb::new(first, first), |acc, (_, bounds)| {
+        let c = center(bounds);
+        surrounding_box(&acc, &aabb::Aabb::new(c, c))
+    });
+    let extent = centers.max() - centers.min();
+    let axis = if extent.x() >= extent.y() && extent.x() >= extent.z() {
+        0
+    } else if extent.y() >= extent.z() {
+        1
+    } else {
+        2
+    };
+    let key = |bounds: &aabb::Aabb| {
+        let c = center(bounds);
+        match axis {
+            0 => c.x(),
+            1 => c.y(),
+            _ => c.z(),
+        }
+    };
+    entries.sort_by(|(_, a), (_, b)| key(a).total_cmp(&key(b)));
+
+    let right = entries.split_off(entries.len() / 2);
+    let left = build(lights, entries);
+    let right = build(lights, right);
+    Node {
+        bounds: surrounding_box(&left.bounds, &right.bounds),
+        power: left.power + right.power,
+        kind: NodeKind::Interior(Box::new(left), Box::new(right)),
+    }
+}
+
+pub struct LightBvh {
+    lights: Vec<Rc<dyn light::Light>>,
+    root: Option<Node>,
+    infinite: Vec<Rc<dyn light::Light>>,
+}
+
+impl LightBvh {
+    pub fn new(lights: &[Rc<dyn light::Light>]) -> LightBvh {
+        let mut bounded = Vec::new();
+        let mut infinite = Vec::new();
+        for light in lights {
+            match light.bounds() {
+                Some(_) => bounded.push(light.clone()),
+                None => infinite.push(light.clone()),
+            }
+        }
+        let entries: Vec<(usize, aabb::Aabb)> = bounded
+            .iter()
+            .enumerate()
+            .filter_map(|(index, light)| light.bounds().map(|bounds| (index, bounds)))
+            .collect();
+        let root = if entries.is_empty() {
+            None
+        } else {
+            Some(build(&bounded, entries))
+        };
+        LightBvh {
+            lights: bounded,
+            root,
+            infinite,
+        }
+    }
+
+    // Lights at infinity, such as the sun, which are sampled at every shading point
+    pub fn infinite(&self) -> &[Rc<dyn light::Light>] {
+        &self.infinite
+    }
+
+    // Pick one bounded light for shading point p, along with the probability of picking it
+    pub fn sample(&self, p: &point::Point3) -> Option<(&Rc<dyn light::Light>, f64)> {
+        let mut node = self.root.as_ref()?;
+        let mut probability = 1.0;
+        loop {
+            match &node.kind {
+                NodeKind::Leaf(index) => return Some((&self.lights[*index], probability)),
+                NodeKind::Interior(left, right) => {
+                    let left_importance = left.importance(p);
+                    let total = left_importance + right.importance(p);
+                    if total <= 0.0 {
+                        return None;
+                    }
+                    let left_probability = left_importance / total;
+                    if utils::random_f64() < left_probability {
+                        probability *= left_probability;
+                        node = left;
+                    } else {
+                        probability *= 1.0 - left_probability;
+                        node = right;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color, vector};
+
+    #[test]
+    fn light_probabilities_sum_to_one_and_match_how_often_each_is_picked() {
+        let mut lights: Vec<Rc<dyn light::Light>> = Vec::new();
+        for i in 0..7 {
+            let position = point::Point3::new(i as f64 * 3.0 - 9.0, (i % 3) as f64, 2.0);
+            let intensity = 1.0 + i as f64;
+            lights.push(Rc::new(light::PointLight::new(
+                position,
+                color::Color::new(intensity, intensity, intensity),
+            )));
+        }
+        lights.push(Rc::new(light::DirectionalLight::new(
+            vector::Vec3::new(0.0, -1.0, 0.0),
+            color::Color::new(1.0, 1.0, 1.0),
+            0.5,
+        )));
+        let bvh = LightBvh::new(&lights);
+        assert_eq!(bvh.infinite().len(), 1);
+
+        let p = point::Point3::new(1.0, 0.0, 0.0);
+        let samples = 200_000;
+        let mut picked = vec![(0usize, 0.0); 7];
+        for _ in 0..samples {
+            let (light, probability) = bvh.sample(&p).unwrap();
+            let index = lights.iter().position(|l| Rc::ptr_eq(l, light)).unwrap();
+            picked[index].0 += 1;
+            picked[index].1 = probability;
+        }
+        let total: f64 = picked.iter().map(|(_, probability)| probability).sum();
+        assert!((total - 1.0).abs() < 1e-9, "probabilities sum to {total}");
+        for (count, probability) in picked {
+            let frequency = count as f64 / samples as f64;
+            assert!((frequency - probability).abs() < 0.01, "{frequency} vs {probability}");
+        }
+    }
+}
@@ -1,18 +1,48 @@
+use raytracing_in_a_weekend_rust::ambient_occlusion::AmbientOcclusion;
 use raytracing_in_a_weekend_rust::camera::CameraBuilder;
+use raytracing_in_a_weekend_rust::debug_view::{DebugMode, DebugView};
+use raytracing_in_a_weekend_rust::integrator::Integrator;
 use raytracing_in_a_weekend_rust::point;
 use raytracing_in_a_weekend_rust::random_scene;
 use raytracing_in_a_weekend_rust::vector;
 use std::env;
 use std::fs::File;
 use std::io;
+use std::rc::Rc;
+
+// The hits mode only exists in builds with the hit-count feature
+const MODES: &str = if cfg!(feature = "hit-count") {
+    "path|ao|normal|geometric-normal|uv|depth|material|front-face|hits"
+} else {
+    "path|ao|normal|geometric-normal|uv|depth|material|front-face"
+};
+
+fn usage(program: &str) -> ! {
+    eprintln!("Usage: {} <filename> [{}]", program, MODES);
+    std::process::exit(1);
+}
 
 fn main() -> io::Result<()> {
     let args: Vec<String> = env::args().collect();
 
-    if args.len() < 2 {
-        eprintln!("Usage: {} <filename>", &args[0]);
-        std::process::exit(1);
+    if args.len() < 2 || args.len() > 3 {
+        usage(&args[0]);
     }
+    // Optional render mode; the path tracer is the camera's default
+    let debug_view = |mode| -> Option<Rc<dyn Integrator>> { Some(Rc::new(DebugView::new(mode))) };
+    let integrator: Option<Rc<dyn Integrator>> = match args.get(2).map_or("path", String::as_str) {
+        "path" => None,
+        "ao" => Some(Rc::new(AmbientOcclusion::new(1.0, 16))),
+        "normal" => debug_view(DebugMode::ShadingNormal),
+        "geometric-normal" => debug_view(DebugMode::GeometricNormal),
+        "uv" => debug_view(DebugMode::Uv),
+        "depth" => debug_view(DebugMode::Depth { max_distance: 30.0 }),
+        "material" => debug_view(DebugMode::MaterialId),
+        "front-face" => debug_view(DebugMode::FrontFace),
+        #[cfg(feature = "hit-count")]
+        "hits" => debug_view(DebugMode::HitCount { max_calls: 1000 }),
+        _ => usage(&args[0]),
+    };
 
     let file_name = &args[1];
     println!("{}", file_name);
@@ -23,7 +53,7 @@ fn main() -> io::Result<()> {
     let camera_look_at = point::Point3::new(0.0, 0.0, 0.0);
     let camera_up_vector = vector::Vec3::new(0.0, 1.0, 0.0);
 
-    let camera = CameraBuilder::default()
+    let mut camera_builder = CameraBuilder::default()
         .image_width(1200)
         .aspect_ratio(16.0 / 9.0)
         .vertical_fov(20.0)
@@ -33,8 +63,11 @@ fn main() -> io::Result<()> {
         .samples_per_pixel(500)
         .max_depth(50)
         .defocus_angle(0.6)
-        .focus_dist(10.0)
-        .build();
+        .focus_dist(10.0);
+    if let Some(integrator) = integrator {
+        camera_builder = camera_builder.integrator(integrator);
+    }
+    let camera = camera_builder.build();
 
     camera.render(&world, &mut file)?;
     Ok(())
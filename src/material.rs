@@ -1,4 +1,4 @@
-pub trait Material: Send + Sync {
+pub trait Material {
     fn scatter(
         &self,
         r_in: &crate::ray::Ray,
@@ -6,4 +6,116 @@ pub trait Material: Send + Sync {
         attenuation: &mut crate::color::Color,
         scattered: &mut crate::ray::Ray,
     ) -> bool;
+
+    // BSDF times the cosine term for light arriving from `direction`, used to weight
+    // shadow rays. None for materials that can't be lit explicitly, such as mirrors and
+    // smooth glass.
+    // Point, spot, directional and IES lights can only reach a surface through this, so
+    // materials returning None, including Subsurface behind its smooth boundary, get no
+    // light from them at all.
+    fn eval(
+        &self,
+        _r_in: &crate::ray::Ray,
+        _rec: &crate::hit_record::HitRecord,
+        _direction: &crate::vector::Vec3,
+    ) -> Option<crate::color::Color> {
+        None
+    }
+
+    // Light given off at the hit point; most materials emit nothing
+    fn emitted(
+        &self,
+        _r_in: &crate::ray::Ray,
+        _rec: &crate::hit_record::HitRecord,
+    ) -> crate::color::Color {
+        crate::color::Color::new(0.0, 0.0, 0.0)
+    }
+
+    // Whether this emitter is also registered on the world as a Light. Paths that just
+    // sampled the lights skip its emission so it isn't counted twice.
+    fn is_registered_light(&self) -> bool {
+        false
+    }
+}
+
+// Helpers for checking a material's scatter and eval against each other
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::Material;
+    use crate::{color, hit_record, point, ray, vector};
+    use std::rc::Rc;
+
+    const SAMPLES: usize = 200_000;
+
+    // Hit at the origin of a surface whose normal is +z, seen from direction `wo` above it
+    pub fn surface_hit(
+        material: Rc<dyn Material>,
+        wo: vector::Vec3,
+        front_face: bool,
+    ) -> (ray::Ray, hit_record::HitRecord) {
+        let normal = vector::Vec3::new(0.0, 0.0, 1.0);
+        let rec = hit_record::HitRecord {
+            p: point::Point3::new(0.0, 0.0, 0.0),
+            normal,
+            geometric_normal: normal,
+            material,
+            t: 1.0,
+            front_face,
+            u: 0.5,
+            v: 0.5,
+            tangent: vector::Vec3::new(1.0, 0.0, 0.0),
+            dpdu: vector::Vec3::new(0.0, 0.0, 0.0),
+            dpdv: vector::Vec3::new(0.0, 0.0, 0.0),
+        };
+        let r_in = ray::Ray::new(point::Point3::new(0.0, 0.0, 0.0) + wo, -wo);
+        (r_in, rec)
+    }
+
+    // Average of weight(wi) * g(wi) over the directions scatter picks, and the same
+    // integral taken over the sphere with eval. They agree when eval describes exactly
+    // what scatter samples.
+    pub fn scatter_and_eval(
+        r_in: &ray::Ray,
+        rec: &hit_record::HitRecord,
+        g: impl Fn(&vector::Vec3) -> f64,
+    ) -> (color::Color, color::Color) {
+        let black = color::Color::new(0.0, 0.0, 0.0);
+        let mut scattered_sum = black;
+        let mut eval_sum = black;
+        for _ in 0..SAMPLES {
+            let mut attenuation = black;
+            let mut scattered = *r_in;
+            if rec.material().scatter(r_in, rec, &mut attenuation, &mut scattered) {
+                scattered_sum += attenuation * g(&vector::unit_vector(scattered.direction()));
+            }
+            // Uniform directions over the sphere have density 1 / (4 pi)
+            let wi = vector::random_unit_vector();
+            let value = rec.material().eval(r_in, rec, &wi).expect("material has no eval");
+            eval_sum += value * (g(&wi) * 4.0 * std::f64::consts::PI);
+        }
+        (scattered_sum / SAMPLES as f64, eval_sum / SAMPLES as f64)
+    }
+
+    pub fn assert_close(actual: color::Color, expected: color::Color, tolerance: f64) {
+        let difference = actual - expected;
+        assert!(
+            difference.x().abs() < tolerance
+                && difference.y().abs() < tolerance
+                && difference.z().abs() < tolerance,
+            "expected {expected:?}, got {actual:?}"
+        );
+    }
+
+    // Check conservation and agreement between scatter and eval for light leaving along wo
+    pub fn check_furnace(material: Rc<dyn Material>, wo: vector::Vec3, tolerance: f64) {
+        let (r_in, rec) = surface_hit(material, vector::unit_vector(wo), true);
+        let (scattered, evaluated) = scatter_and_eval(&r_in, &rec, |_| 1.0);
+        assert_close(scattered, evaluated, tolerance);
+        for albedo in [scattered.x(), scattered.y(), scattered.z()] {
+            assert!(albedo <= 1.0 + tolerance, "reflects {albedo}, more than it receives");
+        }
+        // The split between grazing and steep directions has to match too
+        let (scattered, evaluated) = scatter_and_eval(&r_in, &rec, |wi| wi.z());
+        assert_close(scattered, evaluated, tolerance);
+    }
 }
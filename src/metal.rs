@@ -1,13 +1,45 @@
-use crate::{color, material, vector};
+use crate::{color, material, microfacet, onb, spectrum, thin_film, vector};
 
 pub struct Metal {
     albedo: color::Color,
     fuzz: f64,
+    thin_film: Option<thin_film::ThinFilm>,
 }
 
 impl Metal {
+    // Fuzz of zero is a perfect mirror; above that it is the GGX alpha of a rough conductor,
+    // which spreads reflections about as widely as jittering the mirror direction by it would
     pub fn new(albedo: color::Color, fuzz: f64) -> Metal {
-        Metal { albedo, fuzz }
+        Metal {
+            albedo,
+            fuzz,
+            thin_film: None,
+        }
+    }
+
+    // Coat the metal with a film whose interference tints the reflection, as on anodized metal
+    pub fn with_thin_film(mut self, film: thin_film::ThinFilm) -> Metal {
+        self.thin_film = Some(film);
+        self
+    }
+
+    fn alpha(&self) -> f64 {
+        self.fuzz.clamp(microfacet::MIN_ALPHA, 1.0)
+    }
+
+    // Colour reflected by a (micro)facet seen at cos_theta from its normal
+    fn reflectance(&self, r_in: &crate::ray::Ray, cos_theta: f64) -> color::Color {
+        match self.thin_film {
+            None => self.albedo,
+            Some(film) => match r_in.wavelength() {
+                Some(lambda) => {
+                    let albedo = spectrum::rgb_to_spectrum(self.albedo, lambda);
+                    let reflectance = film.conductor_reflectance(cos_theta, 1.0, albedo, lambda);
+                    color::Color::new(reflectance, reflectance, reflectance)
+                }
+                None => film.conductor_reflectance_rgb(cos_theta, 1.0, self.albedo),
+            },
+        }
     }
 
     pub fn scatter(
@@ -17,11 +49,46 @@ impl Metal {
         attenuation: &mut color::Color,
         scattered: &mut crate::ray::Ray,
     ) -> bool {
-        let reflected = crate::vector::reflect(&r_in.direction(), rec.normal())
-            + self.fuzz * vector::random_unit_vector();
-        *scattered = crate::ray::Ray::new(*rec.p(), reflected);
-        *attenuation = self.albedo;
-        vector::dot(scattered.direction(), *rec.normal()) > 0.0
+        if self.fuzz <= 0.0 {
+            let reflected = crate::vector::reflect(&r_in.direction(), rec.normal());
+            *scattered = crate::ray::Ray::new(*rec.p(), reflected);
+            let cos_theta = vector::dot(-vector::unit_vector(r_in.direction()), *rec.normal());
+            *attenuation = self.reflectance(r_in, cos_theta);
+            return vector::dot(scattered.direction(), *rec.normal()) > 0.0;
+        }
+
+        let frame = onb::Onb::new(rec.normal());
+        let wo = frame.to_local(-vector::unit_vector(r_in.direction()));
+        if wo.z() <= 0.0 {
+            return false;
+        }
+        let Some((wi, m, weight)) = microfacet::sample_reflection(&wo, self.alpha()) else {
+            return false;
+        };
+        *attenuation = self.reflectance(r_in, vector::dot(wo, m)) * weight;
+        *scattered = crate::ray::Ray::new(*rec.p(), frame.local(wi));
+        true
+    }
+
+    pub fn eval(
+        &self,
+        r_in: &crate::ray::Ray,
+        rec: &crate::hit_record::HitRecord,
+        direction: &vector::Vec3,
+    ) -> Option<color::Color> {
+        // A mirror reflects along a single direction that shadow rays never find
+        if self.fuzz <= 0.0 {
+            return None;
+        }
+        let frame = onb::Onb::new(rec.normal());
+        let wo = frame.to_local(-vector::unit_vector(r_in.direction()));
+        let wi = frame.to_local(vector::unit_vector(*direction));
+        let value = microfacet::eval_reflection(&wo, &wi, self.alpha());
+        if value <= 0.0 {
+            return Some(color::Color::new(0.0, 0.0, 0.0));
+        }
+        let half = vector::unit_vector(wo + wi);
+        Some(self.reflectance(r_in, vector::dot(wo, half)) * value)
     }
 }
 
@@ -35,4 +102,27 @@ impl material::Material for Metal {
     ) -> bool {
         self.scatter(r_in, rec, attenuation, scattered)
     }
+
+    fn eval(
+        &self,
+        r_in: &crate::ray::Ray,
+        rec: &crate::hit_record::HitRecord,
+        direction: &vector::Vec3,
+    ) -> Option<color::Color> {
+        self.eval(r_in, rec, direction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::tests::check_furnace;
+    use std::rc::Rc;
+
+    #[test]
+    fn fuzzed_metal_white_furnace() {
+        let material = Rc::new(Metal::new(color::Color::new(0.9, 0.6, 0.3), 0.5));
+        check_furnace(material.clone(), vector::Vec3::new(0.0, 0.0, 1.0), 0.02);
+        check_furnace(material, vector::Vec3::new(1.0, 0.0, 1.0), 0.02);
+    }
 }
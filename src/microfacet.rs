@@ -0,0 +1,214 @@
+// Microfacet helpers shared by the rough materials.
+// All directions are in local shading space, where z is the macro surface normal.
+use crate::{color, utils, vector};
+use std::f64::consts::PI;
+
+// Smallest GGX alpha used, so perfectly smooth surfaces don't divide by zero
+pub const MIN_ALPHA: f64 = 1e-4;
+
+// Perceptual roughness in [0, 1] to GGX alpha
+pub fn roughness_to_alpha(roughness: f64) -> f64 {
+    (roughness * roughness).max(MIN_ALPHA)
+}
+
+// GGX (Trowbridge-Reitz) distribution of microfacet normals
+pub fn ggx_d(m: &vector::Vec3, alpha: f64) -> f64 {
+    let cos2 = m.z() * m.z();
+    if cos2 <= 0.0 {
+        return 0.0;
+    }
+    let tan2 = (1.0 - cos2) / cos2;
+    let alpha2 = alpha * alpha;
+    let denom = alpha2 + tan2;
+    alpha2 / (PI * cos2 * cos2 * denom * denom)
+}
+
+// Smith masking term for direction w seen through microfacet normal m
+pub fn smith_g1(w: &vector::Vec3, m: &vector::Vec3, alpha: f64) -> f64 {
+    if vector::dot(*w, *m) * w.z() <= 0.0 {
+        return 0.0;
+    }
+    let cos2 = w.z() * w.z();
+    let tan2 = (1.0 - cos2).max(0.0) / cos2;
+    2.0 / (1.0 + (1.0 + alpha * alpha * tan2).sqrt())
+}
+
+// Sample a microfacet normal with density D(m) * cos(theta_m)
+pub fn sample_ggx(alpha: f64) -> vector::Vec3 {
+    let u1 = utils::random_f64();
+    let u2 = utils::random_f64();
+    let tan2 = alpha * alpha * u1 / (1.0 - u1);
+    let cos_theta = 1.0 / (1.0 + tan2).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * PI * u2;
+    vector::Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+}
+
+// Anisotropic GGX with separate roughness along the tangent (x) and bitangent (y)
+pub fn ggx_d_anisotropic(m: &vector::Vec3, alpha_x: f64, alpha_y: f64) -> f64 {
+    if m.z() <= 0.0 {
+        return 0.0;
+    }
+    let x = m.x() / alpha_x;
+    let y = m.y() / alpha_y;
+    let denom = x * x + y * y + m.z() * m.z();
+    1.0 / (PI * alpha_x * alpha_y * denom * denom)
+}
+
+pub fn smith_g1_anisotropic(w: &vector::Vec3, m: &vector::Vec3, alpha_x: f64, alpha_y: f64) -> f64 {
+    if vector::dot(*w, *m) * w.z() <= 0.0 {
+        return 0.0;
+    }
+    // alpha^2 * tan^2(theta), with alpha projected onto the direction's azimuth
+    let a2_tan2 = ((w.x() * alpha_x).powi(2) + (w.y() * alpha_y).powi(2)) / (w.z() * w.z());
+    2.0 / (1.0 + (1.0 + a2_tan2).sqrt())
+}
+
+pub fn sample_ggx_anisotropic(alpha_x: f64, alpha_y: f64) -> vector::Vec3 {
+    let u1 = utils::random_f64();
+    let u2 = utils::random_f64();
+    let phi = (alpha_y * (2.0 * PI * u2).sin()).atan2(alpha_x * (2.0 * PI * u2).cos());
+    let (sin_phi, cos_phi) = phi.sin_cos();
+    let alpha2 = 1.0 / ((cos_phi / alpha_x).powi(2) + (sin_phi / alpha_y).powi(2));
+    let tan2 = alpha2 * u1 / (1.0 - u1);
+    let cos_theta = 1.0 / (1.0 + tan2).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    vector::Vec3::new(sin_theta * cos_phi, sin_theta * sin_phi, cos_theta)
+}
+
+// Exact Fresnel reflectance of unpolarized light at a dielectric boundary.
+// cos_i is measured on the incident side, eta is incident index over transmitted index.
+pub fn fresnel_dielectric(cos_i: f64, eta: f64) -> f64 {
+    let cos_i = cos_i.clamp(0.0, 1.0);
+    let sin2_t = eta * eta * (1.0 - cos_i * cos_i);
+    if sin2_t >= 1.0 {
+        // Total internal reflection
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let r_s = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let r_p = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    0.5 * (r_s * r_s + r_p * r_p)
+}
+
+// Schlick's approximation with a coloured reflectance at normal incidence, for conductors
+pub fn schlick_fresnel(f0: color::Color, cosine: f64) -> color::Color {
+    let weight = (1.0 - cosine.clamp(0.0, 1.0)).powi(5);
+    f0 + (color::Color::new(1.0, 1.0, 1.0) - f0) * weight
+}
+
+// Throughput of a direction wi generated through a microfacet normal sampled with
+// sample_ggx, once the Fresnel term has been accounted for separately
+pub fn sample_weight(wo: &vector::Vec3, wi: &vector::Vec3, m: &vector::Vec3, alpha: f64) -> f64 {
+    smith_g1(wo, m, alpha) * smith_g1(wi, m, alpha) * vector::dot(*wo, *m).abs()
+        / (wo.z() * m.z())
+}
+
+// Sample a glossy reflection of wo, returning the direction and its throughput
+pub fn sample_reflection(wo: &vector::Vec3, alpha: f64) -> Option<(vector::Vec3, vector::Vec3, f64)> {
+    let m = sample_ggx(alpha);
+    if vector::dot(*wo, m) <= 0.0 {
+        return None;
+    }
+    let wi = vector::reflect(&-*wo, &m);
+    if wi.z() <= 0.0 {
+        return None;
+    }
+    Some((wi, m, sample_weight(wo, &wi, &m, alpha)))
+}
+
+// Sample reflection or refraction through a rough dielectric boundary, choosing between
+// them with the exact Fresnel term. eta is incident index over transmitted index.
+pub fn sample_dielectric(wo: &vector::Vec3, alpha: f64, eta: f64) -> Option<(vector::Vec3, f64)> {
+    let m = sample_ggx(alpha);
+    let cos_om = vector::dot(*wo, m);
+    if cos_om <= 0.0 {
+        return None;
+    }
+    let fresnel = fresnel_dielectric(cos_om, eta);
+    let reflect = fresnel >= 1.0 || utils::random_f64() < fresnel;
+    let wi = if reflect {
+        vector::reflect(&-*wo, &m)
+    } else {
+        vector::refract(&-*wo, &m, eta)
+    };
+    // Reflections must stay above the surface and refractions must cross it
+    if (wi.z() > 0.0) != reflect {
+        return None;
+    }
+    Some((wi, sample_weight(wo, &wi, &m, alpha)))
+}
+
+// Anisotropic counterpart of sample_reflection, in a frame whose x axis is the tangent
+pub fn sample_reflection_anisotropic(
+    wo: &vector::Vec3,
+    alpha_x: f64,
+    alpha_y: f64,
+) -> Option<(vector::Vec3, vector::Vec3, f64)> {
+    let m = sample_ggx_anisotropic(alpha_x, alpha_y);
+    let cos_om = vector::dot(*wo, m);
+    if cos_om <= 0.0 {
+        return None;
+    }
+    let wi = vector::reflect(&-*wo, &m);
+    if wi.z() <= 0.0 {
+        return None;
+    }
+    let weight = smith_g1_anisotropic(wo, &m, alpha_x, alpha_y)
+        * smith_g1_anisotropic(&wi, &m, alpha_x, alpha_y)
+        * cos_om
+        / (wo.z() * m.z());
+    Some((wi, m, weight))
+}
+
+// Microfacet reflection BRDF times cos(theta_i), without the Fresnel term
+pub fn eval_reflection(wo: &vector::Vec3, wi: &vector::Vec3, alpha: f64) -> f64 {
+    eval_reflection_anisotropic(wo, wi, alpha, alpha)
+}
+
+pub fn eval_reflection_anisotropic(
+    wo: &vector::Vec3,
+    wi: &vector::Vec3,
+    alpha_x: f64,
+    alpha_y: f64,
+) -> f64 {
+    if wo.z() <= 0.0 || wi.z() <= 0.0 {
+        return 0.0;
+    }
+    let m = vector::unit_vector(*wo + *wi);
+    ggx_d_anisotropic(&m, alpha_x, alpha_y)
+        * smith_g1_anisotropic(wo, &m, alpha_x, alpha_y)
+        * smith_g1_anisotropic(wi, &m, alpha_x, alpha_y)
+        / (4.0 * wo.z())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fresnel_at_normal_and_grazing_incidence() {
+        // ((n1 - n2) / (n1 + n2))^2 head on, from either side
+        assert!((fresnel_dielectric(1.0, 1.0 / 1.5) - 0.04).abs() < 1e-12);
+        assert!((fresnel_dielectric(1.0, 1.5) - 0.04).abs() < 1e-12);
+        // Everything is reflected at grazing incidence
+        assert!((fresnel_dielectric(0.0, 1.0 / 1.5) - 1.0).abs() < 1e-12);
+        assert!(fresnel_dielectric(1e-4, 1.0 / 1.5) > 0.99);
+    }
+
+    #[test]
+    fn fresnel_has_no_p_reflection_at_brewster_angle() {
+        let eta: f64 = 1.0 / 1.5;
+        let cos_i = (1.0 / eta).atan().cos();
+        let cos_t = (1.0 - eta * eta * (1.0 - cos_i * cos_i)).sqrt();
+        let r_s = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+        assert!((fresnel_dielectric(cos_i, eta) - 0.5 * r_s * r_s).abs() < 1e-12);
+    }
+
+    #[test]
+    fn fresnel_reflects_everything_past_the_critical_angle() {
+        let critical = (1.0_f64 / 1.5).asin();
+        assert_eq!(fresnel_dielectric((critical + 0.01).cos(), 1.5), 1.0);
+        assert!(fresnel_dielectric((critical - 0.01).cos(), 1.5) < 1.0);
+    }
+}
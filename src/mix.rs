@@ -0,0 +1,129 @@
+use crate::{color, hit_record, material, ray, texture, utils, vector};
+use std::rc::Rc;
+use std::sync::Arc;
+
+// How much of the second material to use at a hit point
+pub enum MixWeight {
+    Constant(f64),
+    // The average of the texture's channels is used, so greyscale masks work as expected
+    Texture(Arc<dyn texture::Texture>),
+}
+
+// Stochastic blend of two materials: each scatter picks one of them with the mix weight
+pub struct Mix {
+    first: Rc<dyn material::Material>,
+    second: Rc<dyn material::Material>,
+    weight: MixWeight,
+}
+
+impl Mix {
+    // `weight` is the fraction of the second material, so 0.3 gives 70% first and 30% second
+    pub fn new(
+        first: Rc<dyn material::Material>,
+        second: Rc<dyn material::Material>,
+        weight: f64,
+    ) -> Mix {
+        Mix {
+            first,
+            second,
+            weight: MixWeight::Constant(weight),
+        }
+    }
+
+    pub fn with_mask(
+        first: Rc<dyn material::Material>,
+        second: Rc<dyn material::Material>,
+        mask: Arc<dyn texture::Texture>,
+    ) -> Mix {
+        Mix {
+            first,
+            second,
+            weight: MixWeight::Texture(mask),
+        }
+    }
+
+    fn weight(&self, rec: &hit_record::HitRecord) -> f64 {
+        match &self.weight {
+            MixWeight::Constant(weight) => *weight,
+            MixWeight::Texture(mask) => {
+                let value = mask.value(rec.u(), rec.v(), rec.p());
+                (value.x() + value.y() + value.z()) / 3.0
+            }
+        }
+    }
+
+    pub fn scatter(
+        &self,
+        r_in: &ray::Ray,
+        rec: &hit_record::HitRecord,
+        attenuation: &mut color::Color,
+        scattered: &mut ray::Ray,
+    ) -> bool {
+        let chosen = if utils::random_f64() < self.weight(rec) {
+            &self.second
+        } else {
+            &self.first
+        };
+        if !chosen.scatter(r_in, rec, attenuation, scattered) {
+            return false;
+        }
+        // A child without eval, such as Metal or Dielectric, isn't light sampled through ours
+        if chosen.eval(r_in, rec, &scattered.direction()).is_none() {
+            *scattered = scattered.outside_eval();
+        }
+        true
+    }
+
+    pub fn eval(
+        &self,
+        r_in: &ray::Ray,
+        rec: &hit_record::HitRecord,
+        direction: &vector::Vec3,
+    ) -> Option<color::Color> {
+        let first = self.first.eval(r_in, rec, direction);
+        let second = self.second.eval(r_in, rec, direction);
+        if first.is_none() && second.is_none() {
+            return None;
+        }
+        let black = color::Color::new(0.0, 0.0, 0.0);
+        let weight = self.weight(rec);
+        Some(first.unwrap_or(black) * (1.0 - weight) + second.unwrap_or(black) * weight)
+    }
+}
+
+impl material::Material for Mix {
+    fn scatter(
+        &self,
+        r_in: &ray::Ray,
+        rec: &hit_record::HitRecord,
+        attenuation: &mut color::Color,
+        scattered: &mut ray::Ray,
+    ) -> bool {
+        self.scatter(r_in, rec, attenuation, scattered)
+    }
+
+    fn eval(
+        &self,
+        r_in: &ray::Ray,
+        rec: &hit_record::HitRecord,
+        direction: &vector::Vec3,
+    ) -> Option<color::Color> {
+        self.eval(r_in, rec, direction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::tests::check_furnace;
+    use crate::{lambertian, metal};
+
+    #[test]
+    fn white_furnace() {
+        let diffuse = Rc::new(lambertian::Lambertian::new(color::Color::new(0.8, 0.5, 0.2)));
+        let glossy = Rc::new(metal::Metal::new(color::Color::new(0.9, 0.9, 0.9), 0.3));
+        let material = Rc::new(Mix::new(diffuse, glossy, 0.3));
+        check_furnace(material.clone(), vector::Vec3::new(0.0, 0.0, 1.0), 0.02);
+        check_furnace(material, vector::Vec3::new(1.0, 0.0, 0.5), 0.02);
+    }
+}
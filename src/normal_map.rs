@@ -0,0 +1,161 @@
+use crate::{hit_record, hittable, interval, material, ray, texture, vector};
+use std::rc::Rc;
+use std::sync::Arc;
+
+// Step in (u, v) used to take finite differences of a height texture
+const BUMP_DELTA: f64 = 1e-3;
+
+pub enum SurfaceDetail {
+    // Tangent-space normals encoded as RGB, with (0.5, 0.5, 1.0) meaning unperturbed
+    NormalMap(Arc<dyn texture::Texture>),
+    // Height field read from the texture's first channel, in scene units times strength
+    BumpMap {
+        height: Arc<dyn texture::Texture>,
+        strength: f64,
+    },
+}
+
+// Wraps a hittable and perturbs the shading normal of its hits before any material sees them.
+// The geometric normal in the hit record is left untouched.
+pub struct NormalMapped {
+    object: Rc<dyn hittable::Hittable>,
+    detail: SurfaceDetail,
+}
+
+impl NormalMapped {
+    pub fn new(object: Rc<dyn hittable::Hittable>, detail: SurfaceDetail) -> NormalMapped {
+        NormalMapped { object, detail }
+    }
+
+    fn perturbed_normal(&self, rec: &hit_record::HitRecord) -> vector::Vec3 {
+        let normal = *rec.normal();
+        let tangent = *rec.tangent();
+        let bitangent = rec.bitangent();
+        // The detail belongs to the outward side; seen from behind, its tilt is mirrored
+        let side = if rec.front_face() { 1.0 } else { -1.0 };
+        match &self.detail {
+            SurfaceDetail::NormalMap(map) => {
+                let encoded = map.value(rec.u(), rec.v(), rec.p());
+                let local = 2.0 * encoded - vector::Vec3::new(1.0, 1.0, 1.0);
+                side * (tangent * local.x() + bitangent * local.y()) + normal * local.z()
+            }
+            SurfaceDetail::BumpMap { height, strength } => {
+                let sample = |u: f64, v: f64| height.value(u, v, rec.p()).x();
+                let h = sample(rec.u(), rec.v());
+                let dh_du = (sample(rec.u() + BUMP_DELTA, rec.v()) - h) / BUMP_DELTA;
+                let dh_dv = (sample(rec.u(), rec.v() + BUMP_DELTA) - h) / BUMP_DELTA;
+                // Slopes per unit of distance on the surface rather than per unit of (u, v),
+                // so the bumps look the same however the surface is parameterized
+                let slope = |dh: f64, dp: &vector::Vec3| {
+                    let length = dp.length();
+                    if length > 0.0 {
+                        dh / length
+                    } else {
+                        0.0
+                    }
+                };
+                let gradient =
+                    slope(dh_du, rec.dpdu()) * tangent + slope(dh_dv, rec.dpdv()) * bitangent;
+                normal - side * *strength * gradient
+            }
+        }
+    }
+}
+
+impl hittable::Hittable for NormalMapped {
+    fn hit(&self, r: &ray::Ray, ray_t: &interval::Interval) -> Option<hit_record::HitRecord> {
+        hittable::count_hit_call();
+        let mut rec = self.object.hit(r, ray_t)?;
+        let mut shading = self.perturbed_normal(&rec);
+        if shading.near_zero() {
+            return Some(rec);
+        }
+        shading = vector::unit_vector(shading);
+
+        // A shading normal facing away from the viewer renders as a black fringe,
+        // so bend it back until the viewer sees its front side
+        let view = -vector::unit_vector(r.direction());
+        let facing = vector::dot(shading, view);
+        if facing < 0.01 {
+            shading = vector::unit_vector(shading + (0.01 - facing) * view);
+        }
+
+        // Keep the tangent frame orthogonal to the new normal
+        let tangent = *rec.tangent() - vector::dot(*rec.tangent(), shading) * shading;
+        if !tangent.near_zero() {
+            rec.tangent = vector::unit_vector(tangent);
+        }
+        rec.normal = shading;
+        Some(rec)
+    }
+
+    fn materials(&self) -> Vec<Rc<dyn material::Material>> {
+        self.object.materials()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color, lambertian, point, quad};
+
+    // Height rising linearly with u and v
+    struct Ramp(f64, f64);
+
+    impl texture::Texture for Ramp {
+        fn value(&self, u: f64, v: f64, _p: &point::Point3) -> color::Color {
+            let h = self.0 * u + self.1 * v;
+            color::Color::new(h, h, h)
+        }
+    }
+
+    struct Constant(color::Color);
+
+    impl texture::Texture for Constant {
+        fn value(&self, _u: f64, _v: f64, _p: &point::Point3) -> color::Color {
+            self.0
+        }
+    }
+
+    // A 2 x 1 quad in the z = 0 plane, with u along x and v along y
+    fn shading_normal(detail: SurfaceDetail, from_above: bool) -> vector::Vec3 {
+        let material = Rc::new(lambertian::Lambertian::new(color::Color::new(0.5, 0.5, 0.5)));
+        let quad = Rc::new(quad::Quad::new(
+            point::Point3::new(0.0, 0.0, 0.0),
+            vector::Vec3::new(2.0, 0.0, 0.0),
+            vector::Vec3::new(0.0, 1.0, 0.0),
+            material,
+        ));
+        let mapped = NormalMapped::new(quad, detail);
+        let z = if from_above { 1.0 } else { -1.0 };
+        let r = ray::Ray::new(point::Point3::new(1.0, 0.5, z), vector::Vec3::new(0.0, 0.0, -z));
+        let rec = hittable::Hittable::hit(&mapped, &r, &interval::Interval::new(0.001, 10.0));
+        *rec.unwrap().normal()
+    }
+
+    fn assert_close(actual: vector::Vec3, expected: vector::Vec3) {
+        assert!((actual - expected).length() < 1e-6, "expected {expected:?}, got {actual:?}");
+    }
+
+    #[test]
+    fn bump_slopes_are_measured_in_scene_units() {
+        // The same height change per unit u as per unit v, but u spans twice the distance
+        let bump = |from_above| {
+            let height = Arc::new(Ramp(0.1, 0.1));
+            shading_normal(SurfaceDetail::BumpMap { height, strength: 1.0 }, from_above)
+        };
+        let expected = vector::unit_vector(vector::Vec3::new(-0.05, -0.1, 1.0));
+        assert_close(bump(true), expected);
+        // From behind, the same bump is seen mirrored
+        assert_close(bump(false), -expected);
+    }
+
+    #[test]
+    fn normal_maps_keep_their_orientation_on_back_faces() {
+        let encoded = color::Color::new(0.5, 0.75, 1.0);
+        let map = || SurfaceDetail::NormalMap(Arc::new(Constant(encoded)));
+        let expected = vector::unit_vector(vector::Vec3::new(0.0, 0.5, 1.0));
+        assert_close(shading_normal(map(), true), expected);
+        assert_close(shading_normal(map(), false), -expected);
+    }
+}
@@ -0,0 +1,62 @@
+use crate::vector;
+
+// Orthonormal basis around a surface normal (w), used to move directions in and out of local shading space
+#[derive(Debug, Clone, Copy)]
+pub struct Onb {
+    u: vector::Vec3,
+    v: vector::Vec3,
+    w: vector::Vec3,
+}
+
+impl Onb {
+    pub fn new(normal: &vector::Vec3) -> Onb {
+        let w = vector::unit_vector(*normal);
+        // Pick any helper axis that is not parallel to w
+        let a = if w.x().abs() > 0.9 {
+            vector::Vec3::new(0.0, 1.0, 0.0)
+        } else {
+            vector::Vec3::new(1.0, 0.0, 0.0)
+        };
+        let v = vector::unit_vector(vector::cross(w, a));
+        let u = vector::cross(w, v);
+        Onb { u, v, w }
+    }
+
+    // Basis with u aligned to a surface tangent, for anisotropic shading
+    pub fn from_tangent(normal: &vector::Vec3, tangent: &vector::Vec3) -> Onb {
+        let w = vector::unit_vector(*normal);
+        let projected = *tangent - vector::dot(*tangent, w) * w;
+        if projected.near_zero() {
+            return Onb::new(normal);
+        }
+        let u = vector::unit_vector(projected);
+        let v = vector::cross(w, u);
+        Onb { u, v, w }
+    }
+
+    pub fn u(&self) -> vector::Vec3 {
+        self.u
+    }
+
+    pub fn v(&self) -> vector::Vec3 {
+        self.v
+    }
+
+    pub fn w(&self) -> vector::Vec3 {
+        self.w
+    }
+
+    // Transform a vector from local coordinates (z along the normal) to world coordinates
+    pub fn local(&self, a: vector::Vec3) -> vector::Vec3 {
+        a.x() * self.u + a.y() * self.v + a.z() * self.w
+    }
+
+    // Transform a world-space vector into local coordinates
+    pub fn to_local(&self, a: vector::Vec3) -> vector::Vec3 {
+        vector::Vec3::new(
+            vector::dot(a, self.u),
+            vector::dot(a, self.v),
+            vector::dot(a, self.w),
+        )
+    }
+}
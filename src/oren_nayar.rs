@@ -0,0 +1,124 @@
+use crate::{color, hit_record, material, onb, ray, vector};
+
+// Oren-Nayar rough diffuse reflection, for clay, concrete and fabric.
+// sigma is the standard deviation of the facet slope angle in radians; zero matches Lambertian.
+pub struct OrenNayar {
+    albedo: color::Color,
+    a: f64,
+    b: f64,
+}
+
+impl OrenNayar {
+    pub fn new(albedo: color::Color, sigma: f64) -> OrenNayar {
+        let sigma2 = sigma * sigma;
+        OrenNayar {
+            albedo,
+            a: 1.0 - 0.5 * sigma2 / (sigma2 + 0.33),
+            b: 0.45 * sigma2 / (sigma2 + 0.09),
+        }
+    }
+
+    // Ratio of the Oren-Nayar BRDF to a Lambertian one with the same albedo
+    fn roughness_factor(&self, wo: &vector::Vec3, wi: &vector::Vec3) -> f64 {
+        let sin_o = (1.0 - wo.z() * wo.z()).max(0.0).sqrt();
+        let sin_i = (1.0 - wi.z() * wi.z()).max(0.0).sqrt();
+        let mut max_cos = 0.0;
+        if sin_o > 1e-4 && sin_i > 1e-4 {
+            let cos_delta_phi = (wi.x() * wo.x() + wi.y() * wo.y()) / (sin_i * sin_o);
+            max_cos = cos_delta_phi.max(0.0);
+        }
+        // alpha is the larger of the two polar angles and beta the smaller
+        let (sin_alpha, tan_beta) = if wi.z().abs() > wo.z().abs() {
+            (sin_o, sin_i / wi.z().abs())
+        } else {
+            (sin_i, sin_o / wo.z().abs())
+        };
+        self.a + self.b * max_cos * sin_alpha * tan_beta
+    }
+
+    pub fn scatter(
+        &self,
+        r_in: &ray::Ray,
+        hit_record: &hit_record::HitRecord,
+        attenuation: &mut color::Color,
+        scattered: &mut ray::Ray,
+    ) -> bool {
+        let frame = onb::Onb::new(hit_record.normal());
+        let wo = frame.to_local(-vector::unit_vector(r_in.direction()));
+        // Cosine-weighted sampling cancels the cosine and 1/pi, leaving albedo times the roughness factor
+        let wi = vector::random_cosine_direction();
+        *scattered = ray::Ray::new(*hit_record.p(), frame.local(wi));
+        *attenuation = self.albedo * self.roughness_factor(&wo, &wi);
+        true
+    }
+
+    pub fn eval(
+        &self,
+        r_in: &ray::Ray,
+        hit_record: &hit_record::HitRecord,
+        direction: &vector::Vec3,
+    ) -> Option<color::Color> {
+        let frame = onb::Onb::new(hit_record.normal());
+        let wo = frame.to_local(-vector::unit_vector(r_in.direction()));
+        let wi = frame.to_local(vector::unit_vector(*direction));
+        if wi.z() <= 0.0 {
+            return Some(color::Color::new(0.0, 0.0, 0.0));
+        }
+        Some(self.albedo * (self.roughness_factor(&wo, &wi) * wi.z() / std::f64::consts::PI))
+    }
+}
+
+impl material::Material for OrenNayar {
+    fn scatter(
+        &self,
+        r_in: &ray::Ray,
+        rec: &hit_record::HitRecord,
+        attenuation: &mut color::Color,
+        scattered: &mut ray::Ray,
+    ) -> bool {
+        self.scatter(r_in, rec, attenuation, scattered)
+    }
+
+    fn eval(
+        &self,
+        r_in: &ray::Ray,
+        rec: &hit_record::HitRecord,
+        direction: &vector::Vec3,
+    ) -> Option<color::Color> {
+        self.eval(r_in, rec, direction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lambertian;
+    use crate::material::tests::{assert_close, check_furnace, surface_hit};
+    use std::rc::Rc;
+
+    #[test]
+    fn white_furnace() {
+        let material = Rc::new(OrenNayar::new(color::Color::new(0.8, 0.5, 0.2), 0.5));
+        check_furnace(material.clone(), vector::Vec3::new(0.0, 0.0, 1.0), 0.02);
+        check_furnace(material, vector::Vec3::new(1.0, 0.0, 0.3), 0.02);
+    }
+
+    #[test]
+    fn smooth_surface_matches_lambertian() {
+        let albedo = color::Color::new(0.8, 0.5, 0.2);
+        let material = Rc::new(OrenNayar::new(albedo, 0.0));
+        let (r_in, rec) = surface_hit(material.clone(), vector::Vec3::new(1.0, 0.0, 1.0), true);
+        let lambertian = lambertian::Lambertian::new(albedo);
+        for direction in [
+            vector::Vec3::new(0.0, 0.0, 1.0),
+            vector::Vec3::new(-1.0, 0.5, 0.2),
+            vector::Vec3::new(0.3, -0.7, 0.6),
+        ] {
+            assert_close(
+                material.eval(&r_in, &rec, &direction).unwrap(),
+                lambertian.eval(&r_in, &rec, &direction).unwrap(),
+                1e-12,
+            );
+        }
+    }
+}
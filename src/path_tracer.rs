@@ -0,0 +1,270 @@
+use crate::{
+    background, color, global_medium, hittable, hittable_list, integrator, interval, light, point,
+    ray, spectrum, utils, vector,
+};
+
+// Unidirectional path tracer with next-event estimation towards the registered lights,
+// optional global fog and Russian roulette. This is what cameras render with by default.
+#[derive(Debug, Clone)]
+pub struct PathTracer {
+    max_depth: u32,
+    roulette_depth: u32,
+    spectral: bool,
+    medium: Option<global_medium::GlobalMedium>,
+    background: background::Background,
+}
+
+impl PathTracer {
+    // Russian roulette is what ends paths; max_depth is only a safety limit and should sit
+    // far beyond the depths roulette lets paths reach, or deep paths through glass go missing
+    pub fn new(max_depth: u32) -> PathTracer {
+        PathTracer {
+            max_depth,
+            roulette_depth: 3,
+            spectral: false,
+            medium: None,
+            background: background::Background::Gradient,
+        }
+    }
+
+    // Number of bounces every path makes before Russian roulette may end it
+    pub fn with_roulette_depth(mut self, roulette_depth: u32) -> PathTracer {
+        self.roulette_depth = roulette_depth;
+        self
+    }
+
+    // Trace one wavelength per path instead of RGB
+    pub fn with_spectral(mut self, spectral: bool) -> PathTracer {
+        self.spectral = spectral;
+        self
+    }
+
+    pub fn with_medium(mut self, medium: global_medium::GlobalMedium) -> PathTracer {
+        self.medium = Some(medium);
+        self
+    }
+
+    pub fn with_background(mut self, background: background::Background) -> PathTracer {
+        self.background = background;
+        self
+    }
+
+    // Light reaching p directly from the registered lights, through surfaces and fog: every
+    // light at infinity plus one other light picked by the light hierarchy. `response` turns
+    // a direction towards a light into the fraction scattered to the viewer, or None when
+    // the scattering can't be lit explicitly, in which case no light is sampled at all.
+    fn sample_lights<F: Fn(&vector::Vec3) -> Option<color::Color>>(
+        &self,
+        p: &point::Point3,
+        ray: &ray::Ray,
+        world: &hittable_list::HittableList,
+        response: F,
+    ) -> Option<color::Color> {
+        response(&-ray.direction())?;
+        let lights = world.light_bvh();
+        let mut direct = color::Color::new(0.0, 0.0, 0.0);
+        for light in lights.infinite() {
+            direct += self.light_contribution(light.as_ref(), p, ray, world, &response);
+        }
+        if let Some((light, probability)) = lights.sample(p) {
+            direct +=
+                self.light_contribution(light.as_ref(), p, ray, world, &response) / probability;
+        }
+        Some(direct)
+    }
+
+    // One shadow ray towards `light`
+    fn light_contribution<F: Fn(&vector::Vec3) -> Option<color::Color>>(
+        &self,
+        light: &dyn light::Light,
+        p: &point::Point3,
+        ray: &ray::Ray,
+        world: &hittable_list::HittableList,
+        response: &F,
+    ) -> color::Color {
+        let black = color::Color::new(0.0, 0.0, 0.0);
+        let Some(sample) = light.sample(p, ray.wavelength()) else {
+            return black;
+        };
+        let scattered = response(&sample.direction).unwrap_or(black);
+        if scattered.near_zero() {
+            return black;
+        }
+        let shadow_ray = ray::Ray::new(*p, sample.direction).with_wavelength(ray.wavelength());
+        let shadow_t = interval::Interval::new(0.001, sample.distance * (1.0 - 1e-6));
+        let mut visibility = hittable::Hittable::transmittance(world, &shadow_ray, &shadow_t);
+        if let Some(medium) = &self.medium {
+            visibility *= medium.transmittance(&shadow_ray, &shadow_t);
+        }
+        if visibility <= 0.0 {
+            return black;
+        }
+        self.path_color(sample.radiance, ray) * self.path_color(scattered, ray) * visibility
+    }
+
+    // In spectral mode every RGB quantity on a path is upsampled to the path's wavelength
+    fn path_color(&self, color: color::Color, ray: &ray::Ray) -> color::Color {
+        match ray.wavelength() {
+            Some(lambda) if self.spectral => {
+                let value = spectrum::rgb_to_spectrum(color, lambda);
+                color::Color::new(value, value, value)
+            }
+            _ => color,
+        }
+    }
+
+    // Russian roulette: past the minimum depth, end dim paths at random and boost the
+    // survivors so the estimate stays unbiased. Survival is capped at 0.95, so even bright
+    // paths almost never reach a max_depth in the hundreds; the only bias is from the paths
+    // that do. Returns false when the path ends.
+    fn survives_roulette(&self, throughput: &mut color::Color, depth: u32) -> bool {
+        if depth < self.roulette_depth {
+            return true;
+        }
+        let survival = throughput.x().max(throughput.y()).max(throughput.z()).min(0.95);
+        if survival <= 0.0 || utils::random_f64() >= survival {
+            return false;
+        }
+        *throughput = *throughput / survival;
+        true
+    }
+}
+
+impl integrator::Integrator for PathTracer {
+    // Follow one path from the camera, adding up the light it collects at every vertex
+    // weighted by the throughput of the path so far
+    fn radiance(&self, ray: &ray::Ray, world: &hittable_list::HittableList) -> color::Color {
+        let black = color::Color::new(0.0, 0.0, 0.0);
+        let mut radiance = black;
+        let mut throughput = color::Color::new(1.0, 1.0, 1.0);
+        let mut ray = *ray;
+        // Whether the vertex the ray left from already gathered the registered lights
+        let mut lights_sampled = false;
+
+        for depth in 0..self.max_depth {
+            let hit_record = hittable::Hittable::hit(
+                world,
+                &ray,
+                &interval::Interval::new(0.001, f64::INFINITY),
+            );
+
+            // Scattering in the global fog happens before the ray reaches the surface it hit
+            if let Some(medium) = &self.medium {
+                let t_surface = hit_record.as_ref().map_or(f64::INFINITY, |record| record.t());
+                if let Some(t) =
+                    medium.sample_scattering(&ray, &interval::Interval::new(0.001, t_surface))
+                {
+                    let p = ray.at(t);
+                    let direct = self
+                        .sample_lights(&p, &ray, world, |direction| {
+                            let phase = medium.phase(&ray.direction(), direction);
+                            Some(color::Color::new(phase, phase, phase))
+                        })
+                        .unwrap_or(black);
+                    throughput = throughput * self.path_color(medium.albedo(), &ray);
+                    radiance += throughput * direct;
+                    ray = ray::Ray::new(p, medium.sample_direction(&ray.direction()))
+                        .with_wavelength(ray.wavelength())
+                        .with_path_wavelength(ray.path_wavelength());
+                    lights_sampled = true;
+                    if !self.survives_roulette(&mut throughput, depth) {
+                        break;
+                    }
+                    continue;
+                }
+            }
+
+            let Some(record) = hit_record else {
+                let background = self.background.color(&ray.direction(), lights_sampled);
+                radiance += throughput * self.path_color(background, &ray);
+                break;
+            };
+
+            // Emitters that are also registered lights were already counted by the shadow rays
+            if !(lights_sampled && record.material().is_registered_light()) {
+                radiance +=
+                    throughput * self.path_color(record.material().emitted(&ray, &record), &ray);
+            }
+            // Light arriving straight from the registered lights, for materials that support it
+            let direct = self.sample_lights(record.p(), &ray, world, |direction| {
+                record.material().eval(&ray, &record, direction)
+            });
+            radiance += throughput * direct.unwrap_or(black);
+
+            let mut scattered_ray = ray::Ray::new(
+                point::Point3::new(0.0, 0.0, 0.0),
+                vector::Vec3::new(0.0, 0.0, 0.0),
+            );
+            let mut attenuation = black;
+            if !record
+                .material()
+                .scatter(&ray, &record, &mut attenuation, &mut scattered_ray)
+                || record.shading_mismatch(&scattered_ray.direction())
+            {
+                break;
+            }
+            throughput = throughput * self.path_color(attenuation, &ray);
+            // Keep the path's wavelength unless the material just picked one
+            ray = scattered_ray
+                .with_wavelength(scattered_ray.wavelength().or(ray.wavelength()))
+                .with_path_wavelength(ray.path_wavelength());
+            // Registered lights hit next were already sampled, unless eval left the lobe out
+            lights_sampled = direct.is_some() && ray.covered_by_eval();
+            if !self.survives_roulette(&mut throughput, depth) {
+                break;
+            }
+        }
+        radiance
+    }
+
+    fn spectral(&self) -> bool {
+        self.spectral
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aabb;
+    use crate::integrator::Integrator;
+    use crate::material::tests::assert_close;
+
+    // Mean radiance along one ray starting in a cube of fog under a uniform background
+    fn mean_radiance(path_tracer: &PathTracer, samples: usize) -> color::Color {
+        let world = hittable_list::HittableList::default();
+        let origin = point::Point3::new(0.0, 0.0, 0.0);
+        let ray = ray::Ray::new(origin, vector::Vec3::new(0.0, 0.0, 1.0));
+        let mut sum = color::Color::new(0.0, 0.0, 0.0);
+        for _ in 0..samples {
+            sum += path_tracer.radiance(&ray, &world);
+        }
+        sum / samples as f64
+    }
+
+    fn foggy_furnace(albedo: f64, roulette_depth: u32) -> PathTracer {
+        let bounds = aabb::Aabb::new(
+            point::Point3::new(-1.0, -1.0, -1.0),
+            point::Point3::new(1.0, 1.0, 1.0),
+        );
+        let albedo = color::Color::new(albedo, albedo, albedo);
+        let medium = global_medium::GlobalMedium::new(2.0, albedo).with_bounds(bounds);
+        PathTracer::new(1000)
+            .with_roulette_depth(roulette_depth)
+            .with_medium(medium)
+            .with_background(background::Background::Solid(color::Color::new(0.5, 1.0, 2.0)))
+    }
+
+    #[test]
+    fn roulette_keeps_a_white_furnace_at_the_background_radiance() {
+        // Fog that never absorbs passes the background on unchanged however often it scatters
+        let radiance = mean_radiance(&foggy_furnace(1.0, 0), 100_000);
+        assert_close(radiance, color::Color::new(0.5, 1.0, 2.0), 0.02);
+    }
+
+    #[test]
+    fn roulette_does_not_change_the_expected_radiance() {
+        let with_roulette = mean_radiance(&foggy_furnace(0.7, 0), 100_000);
+        let without_roulette = mean_radiance(&foggy_furnace(0.7, 1000), 100_000);
+        assert_close(with_roulette, without_roulette, 0.02);
+    }
+}
@@ -0,0 +1,346 @@
+use crate::{color, interval, material, microfacet, onb, ray, utils, vector};
+
+// Index of refraction of the clearcoat layer
+const CLEARCOAT_IOR: f64 = 1.5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Lobe {
+    Clearcoat,
+    Metallic,
+    Transmission,
+    Specular,
+    Diffuse,
+}
+
+// Disney-style uber material combining diffuse, sheen, specular, metallic, clearcoat
+// and transmission lobes. Build it with PrincipledBuilder.
+#[derive(Debug, Clone)]
+pub struct Principled {
+    base_color: color::Color,
+    metallic: f64,
+    alpha: f64,
+    specular: f64,
+    specular_tint: f64,
+    sheen: f64,
+    sheen_tint: f64,
+    clearcoat: f64,
+    clearcoat_alpha: f64,
+    transmission: f64,
+    refractive_index: f64,
+}
+
+impl Principled {
+    // Fraction of energy handled by each lobe for light leaving along cos_o.
+    // The fractions sum to one, so they double as the lobe selection probabilities.
+    fn lobe_weights(&self, cos_o: f64) -> [(Lobe, f64); 5] {
+        let clearcoat = self.clearcoat * microfacet::fresnel_dielectric(cos_o, 1.0 / CLEARCOAT_IOR);
+        let base = 1.0 - clearcoat;
+        let dielectric = base * (1.0 - self.metallic);
+        let transmission = dielectric * self.transmission;
+        let opaque = dielectric - transmission;
+        let f0 = self.specular_f0();
+        let specular = opaque * (f0 + (1.0 - f0) * (1.0 - cos_o).powi(5));
+        [
+            (Lobe::Clearcoat, clearcoat),
+            (Lobe::Metallic, base * self.metallic),
+            (Lobe::Transmission, transmission),
+            (Lobe::Specular, specular),
+            (Lobe::Diffuse, opaque - specular),
+        ]
+    }
+
+    // Whether light can pass into the object, so back faces are seen from inside it
+    fn is_transmissive(&self) -> bool {
+        self.transmission > 0.0 && self.metallic < 1.0
+    }
+
+    // Reflectance at normal incidence of the dielectric specular lobe
+    fn specular_f0(&self) -> f64 {
+        0.08 * self.specular
+    }
+
+    fn tint(&self) -> color::Color {
+        let luminance = 0.2126 * self.base_color.x()
+            + 0.7152 * self.base_color.y()
+            + 0.0722 * self.base_color.z();
+        if luminance > 0.0 {
+            self.base_color / luminance
+        } else {
+            color::Color::new(1.0, 1.0, 1.0)
+        }
+    }
+
+    fn mix(a: color::Color, b: color::Color, t: f64) -> color::Color {
+        a * (1.0 - t) + b * t
+    }
+
+    pub fn scatter(
+        &self,
+        r_in: &ray::Ray,
+        rec: &crate::hit_record::HitRecord,
+        attenuation: &mut color::Color,
+        scattered: &mut ray::Ray,
+    ) -> bool {
+        let frame = onb::Onb::new(rec.normal());
+        let wo = frame.to_local(-vector::unit_vector(r_in.direction()));
+        if wo.z() <= 0.0 {
+            return false;
+        }
+
+        // Rays inside a transmissive object only see the dielectric boundary on the way out.
+        // Back faces of opaque surfaces, such as a quad seen from behind, shade like the front.
+        if !rec.front_face() && self.is_transmissive() {
+            let Some((wi, weight)) =
+                microfacet::sample_dielectric(&wo, self.alpha, self.refractive_index)
+            else {
+                return false;
+            };
+            *attenuation = color::Color::new(weight, weight, weight);
+            *scattered = ray::Ray::new(*rec.p(), frame.local(wi));
+            return true;
+        }
+
+        // Pick one lobe in proportion to its share of the energy
+        let weights = self.lobe_weights(wo.z());
+        let mut choice = utils::random_f64();
+        let mut lobe = Lobe::Diffuse;
+        for (candidate, weight) in weights {
+            if choice < weight {
+                lobe = candidate;
+                break;
+            }
+            choice -= weight;
+        }
+
+        let white = color::Color::new(1.0, 1.0, 1.0);
+        let sampled = match lobe {
+            Lobe::Clearcoat => microfacet::sample_reflection(&wo, self.clearcoat_alpha)
+                .map(|(wi, _, weight)| (wi, white * weight)),
+            Lobe::Metallic => {
+                microfacet::sample_reflection(&wo, self.alpha).map(|(wi, m, weight)| {
+                    let fresnel =
+                        microfacet::schlick_fresnel(self.base_color, vector::dot(wo, m));
+                    (wi, fresnel * weight)
+                })
+            }
+            Lobe::Specular => microfacet::sample_reflection(&wo, self.alpha).map(|(wi, _, weight)| {
+                (wi, Self::mix(white, self.tint(), self.specular_tint) * weight)
+            }),
+            Lobe::Transmission => {
+                microfacet::sample_dielectric(&wo, self.alpha, 1.0 / self.refractive_index).map(
+                    |(wi, weight)| {
+                        // Only light that enters the surface picks up the base colour
+                        let tint = if wi.z() < 0.0 { self.base_color } else { white };
+                        (wi, tint * weight)
+                    },
+                )
+            }
+            Lobe::Diffuse => {
+                let wi = vector::random_cosine_direction();
+                let half = vector::unit_vector(wi + wo);
+                let sheen_color = Self::mix(white, self.tint(), self.sheen_tint);
+                // Sheen adds a grazing retro-reflective rim on top of the Lambertian base
+                let sheen = sheen_color
+                    * (self.sheen
+                        * std::f64::consts::PI
+                        * (1.0 - vector::dot(wi, half)).powi(5));
+                Some((wi, self.base_color + sheen))
+            }
+        };
+
+        let Some((wi, throughput)) = sampled else {
+            return false;
+        };
+        *attenuation = throughput;
+        *scattered = ray::Ray::new(*rec.p(), frame.local(wi));
+        // eval leaves the transmission lobe out, so what it finds isn't light sampled
+        if lobe == Lobe::Transmission {
+            *scattered = scattered.outside_eval();
+        }
+        true
+    }
+
+    pub fn eval(
+        &self,
+        r_in: &ray::Ray,
+        rec: &crate::hit_record::HitRecord,
+        direction: &vector::Vec3,
+    ) -> Option<color::Color> {
+        // Light can't be sampled from inside a transmissive object
+        if !rec.front_face() && self.is_transmissive() {
+            return None;
+        }
+        let black = color::Color::new(0.0, 0.0, 0.0);
+        let frame = onb::Onb::new(rec.normal());
+        let wo = frame.to_local(-vector::unit_vector(r_in.direction()));
+        let wi = frame.to_local(vector::unit_vector(*direction));
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return Some(black);
+        }
+
+        // Sum of every reflective lobe, each weighted by its share of the energy
+        let white = color::Color::new(1.0, 1.0, 1.0);
+        let half = vector::unit_vector(wo + wi);
+        let glossy = microfacet::eval_reflection(&wo, &wi, self.alpha);
+        let mut value = black;
+        for (lobe, weight) in self.lobe_weights(wo.z()) {
+            value += weight
+                * match lobe {
+                    Lobe::Clearcoat => {
+                        white * microfacet::eval_reflection(&wo, &wi, self.clearcoat_alpha)
+                    }
+                    Lobe::Metallic => {
+                        microfacet::schlick_fresnel(self.base_color, vector::dot(wo, half)) * glossy
+                    }
+                    Lobe::Specular => Self::mix(white, self.tint(), self.specular_tint) * glossy,
+                    Lobe::Transmission => black,
+                    Lobe::Diffuse => {
+                        let sheen = Self::mix(white, self.tint(), self.sheen_tint)
+                            * (self.sheen * (1.0 - vector::dot(wi, half)).powi(5));
+                        (self.base_color / std::f64::consts::PI + sheen) * wi.z()
+                    }
+                };
+        }
+        Some(value)
+    }
+}
+
+impl material::Material for Principled {
+    fn scatter(
+        &self,
+        r_in: &crate::ray::Ray,
+        rec: &crate::hit_record::HitRecord,
+        attenuation: &mut color::Color,
+        scattered: &mut crate::ray::Ray,
+    ) -> bool {
+        self.scatter(r_in, rec, attenuation, scattered)
+    }
+
+    fn eval(
+        &self,
+        r_in: &crate::ray::Ray,
+        rec: &crate::hit_record::HitRecord,
+        direction: &vector::Vec3,
+    ) -> Option<color::Color> {
+        self.eval(r_in, rec, direction)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PrincipledBuilder {
+    base_color: color::Color,
+    metallic: f64,
+    roughness: f64,
+    specular: f64,
+    specular_tint: f64,
+    sheen: f64,
+    sheen_tint: f64,
+    clearcoat: f64,
+    clearcoat_roughness: f64,
+    transmission: f64,
+    refractive_index: f64,
+}
+
+impl Default for PrincipledBuilder {
+    fn default() -> Self {
+        Self {
+            base_color: color::Color::new(0.8, 0.8, 0.8),
+            metallic: 0.0,
+            roughness: 0.5,
+            specular: 0.5,
+            specular_tint: 0.0,
+            sheen: 0.0,
+            sheen_tint: 0.5,
+            clearcoat: 0.0,
+            clearcoat_roughness: 0.03,
+            transmission: 0.0,
+            refractive_index: 1.5,
+        }
+    }
+}
+
+impl PrincipledBuilder {
+    pub fn base_color(mut self, base_color: color::Color) -> Self {
+        self.base_color = base_color;
+        self
+    }
+    pub fn metallic(mut self, metallic: f64) -> Self {
+        self.metallic = metallic;
+        self
+    }
+    pub fn roughness(mut self, roughness: f64) -> Self {
+        self.roughness = roughness;
+        self
+    }
+    pub fn specular(mut self, specular: f64) -> Self {
+        self.specular = specular;
+        self
+    }
+    pub fn specular_tint(mut self, specular_tint: f64) -> Self {
+        self.specular_tint = specular_tint;
+        self
+    }
+    pub fn sheen(mut self, sheen: f64) -> Self {
+        self.sheen = sheen;
+        self
+    }
+    pub fn sheen_tint(mut self, sheen_tint: f64) -> Self {
+        self.sheen_tint = sheen_tint;
+        self
+    }
+    pub fn clearcoat(mut self, clearcoat: f64) -> Self {
+        self.clearcoat = clearcoat;
+        self
+    }
+    pub fn clearcoat_roughness(mut self, clearcoat_roughness: f64) -> Self {
+        self.clearcoat_roughness = clearcoat_roughness;
+        self
+    }
+    pub fn transmission(mut self, transmission: f64) -> Self {
+        self.transmission = transmission;
+        self
+    }
+    pub fn refractive_index(mut self, refractive_index: f64) -> Self {
+        self.refractive_index = refractive_index;
+        self
+    }
+    pub fn build(self) -> Principled {
+        let unit = interval::Interval::new(0.0, 1.0);
+        Principled {
+            base_color: self.base_color,
+            metallic: unit.clamp(self.metallic),
+            alpha: microfacet::roughness_to_alpha(unit.clamp(self.roughness)),
+            specular: unit.clamp(self.specular),
+            specular_tint: unit.clamp(self.specular_tint),
+            sheen: self.sheen.max(0.0),
+            sheen_tint: unit.clamp(self.sheen_tint),
+            clearcoat: unit.clamp(self.clearcoat),
+            clearcoat_alpha: microfacet::roughness_to_alpha(unit.clamp(self.clearcoat_roughness)),
+            transmission: unit.clamp(self.transmission),
+            refractive_index: self.refractive_index,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::tests::check_furnace;
+    use std::rc::Rc;
+
+    #[test]
+    fn opaque_lobes_white_furnace() {
+        let material = Rc::new(
+            PrincipledBuilder::default()
+                .base_color(color::Color::new(0.8, 0.5, 0.2))
+                .metallic(0.3)
+                .roughness(0.4)
+                .sheen(0.5)
+                .clearcoat(0.5)
+                .clearcoat_roughness(0.2)
+                .build(),
+        );
+        check_furnace(material.clone(), vector::Vec3::new(0.0, 0.0, 1.0), 0.02);
+        check_furnace(material, vector::Vec3::new(1.0, 0.0, 0.5), 0.02);
+    }
+}
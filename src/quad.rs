@@ -0,0 +1,79 @@
+use crate::{hit_record, hittable, interval, material, point, ray, vector};
+use std::rc;
+
+// Parallelogram spanned by edges u and v from corner q, e.g. for leaves, fences and walls
+pub struct Quad {
+    q: point::Point3,
+    u: vector::Vec3,
+    v: vector::Vec3,
+    // Scaled plane normal used to project hit points onto the (u, v) edges
+    w: vector::Vec3,
+    normal: vector::Vec3,
+    d: f64,
+    material: rc::Rc<dyn material::Material>,
+}
+
+impl Quad {
+    pub fn new(
+        q: point::Point3,
+        u: vector::Vec3,
+        v: vector::Vec3,
+        material: rc::Rc<dyn material::Material>,
+    ) -> Quad {
+        let n = vector::cross(u, v);
+        let normal = vector::unit_vector(n);
+        Quad {
+            q,
+            u,
+            v,
+            w: n / vector::dot(n, n),
+            normal,
+            d: vector::dot(normal, q),
+            material,
+        }
+    }
+}
+
+impl hittable::Hittable for Quad {
+    fn hit(&self, r: &ray::Ray, ray_t: &interval::Interval) -> Option<hit_record::HitRecord> {
+        hittable::count_hit_call();
+        let denom = vector::dot(self.normal, r.direction());
+        // Rays parallel to the plane never hit it
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+        let t = (self.d - vector::dot(self.normal, r.origin())) / denom;
+        if !ray_t.surrounds(t) {
+            return None;
+        }
+
+        // Planar coordinates of the hit point along the two edges
+        let p = r.at(t);
+        let planar = p - self.q;
+        let alpha = vector::dot(self.w, vector::cross(planar, self.v));
+        let beta = vector::dot(self.w, vector::cross(self.u, planar));
+        let unit = interval::Interval::new(0.0, 1.0);
+        if !unit.contains(alpha) || !unit.contains(beta) {
+            return None;
+        }
+
+        let (front_face, normal) = hit_record::face_normal(r, &self.normal);
+        Some(hit_record::HitRecord {
+            p,
+            normal,
+            geometric_normal: normal,
+            t,
+            front_face,
+            material: self.material.clone(),
+            u: alpha,
+            v: beta,
+            tangent: vector::unit_vector(self.u),
+            dpdu: self.u,
+            dpdv: self.v,
+        })
+    }
+
+    fn materials(&self) -> Vec<rc::Rc<dyn material::Material>> {
+        vec![self.material.clone()]
+    }
+}
@@ -1,7 +1,20 @@
-use crate::{color, dielectric, hittable, hittable_list, lambertian, material, metal, point, sphere, vector};
+use crate::{
+    blackbody, color, dielectric, diffuse_light, hittable, hittable_list, lambertian, light, material,
+    metal, point, sphere, utils, vector,
+};
 use std::rc::Rc;
 
 pub fn random_scene() -> hittable_list::HittableList {
+    build_random_scene(0.0)
+}
+
+// The same scene with a share of the small balls glowing at random colour temperatures.
+// Each glowing ball is registered as a sphere light, so it looks best under a dark background.
+pub fn glowing_random_scene() -> hittable_list::HittableList {
+    build_random_scene(0.3)
+}
+
+fn build_random_scene(glow_probability: f64) -> hittable_list::HittableList {
     let mut world = hittable_list::HittableList::new(vec![]);
 
     let ground_color = color::Color::new(0.5, 0.5, 0.5);
@@ -26,6 +39,19 @@ pub fn random_scene() -> hittable_list::HittableList {
                 b as f64 + 0.9 * rand::random::<f64>(),
             );
             if (center - point::Point3::new(4.0, 0.2, 0.0)).length() > 0.9 {
+                // Only draw for the glow when it can happen, so the plain scene keeps its layout
+                if glow_probability > 0.0 && rand::random::<f64>() < glow_probability {
+                    let glow = blackbody::Blackbody::new(utils::random_f64_in_range(1500.0, 9000.0));
+                    let intensity = 4.0;
+                    let emitter = diffuse_light::DiffuseLight::blackbody(glow, intensity)
+                        .registered_as_light();
+                    world.add(Rc::new(sphere::Sphere::new(center, 0.2, Rc::new(emitter)))
+                        as Rc<dyn hittable::Hittable>);
+                    world.add_light(Rc::new(light::SphereLight::blackbody(
+                        center, 0.2, glow, intensity,
+                    )));
+                    continue;
+                }
                 let sphere_material: Rc<dyn material::Material> = if choose_mat < 0.8 {
                     // Diffuse
                     let albedo = vector::random() * vector::random();
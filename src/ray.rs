@@ -1,14 +1,43 @@
-use crate::{color, point, vector};
+use crate::{point, vector};
 #[derive(Debug, Clone, Copy, PartialEq)]
 
 pub struct Ray {
     orig: point::Point3,
     dir: vector::Vec3,
+    // Wavelength in nanometres carried by the path, once one has been sampled
+    wavelength: Option<f64>,
+    // Wavelength the camera drew for the path; RGB paths switch to it at a dispersive surface
+    path_wavelength: Option<f64>,
+    // Whether the material that scattered this ray sampled a lobe its eval covers, so light
+    // sampling already gathered what the ray may hit on a registered light
+    covered_by_eval: bool,
 }
 
 impl Ray {
     pub fn new(orig: point::Point3, dir: vector::Vec3) -> Self {
-        Ray { orig, dir }
+        Ray {
+            orig,
+            dir,
+            wavelength: None,
+            path_wavelength: None,
+            covered_by_eval: true,
+        }
+    }
+
+    pub fn with_wavelength(mut self, wavelength: Option<f64>) -> Self {
+        self.wavelength = wavelength;
+        self
+    }
+
+    pub fn with_path_wavelength(mut self, wavelength: Option<f64>) -> Self {
+        self.path_wavelength = wavelength;
+        self
+    }
+
+    // Mark the ray as scattered from a lobe, such as a mirror or refraction, that eval leaves out
+    pub fn outside_eval(mut self) -> Self {
+        self.covered_by_eval = false;
+        self
     }
 
     pub fn origin(&self) -> point::Point3 {
@@ -19,19 +48,20 @@ impl Ray {
         self.dir
     }
 
+    pub fn wavelength(&self) -> Option<f64> {
+        self.wavelength
+    }
+
+    pub fn path_wavelength(&self) -> Option<f64> {
+        self.path_wavelength
+    }
+
+    pub fn covered_by_eval(&self) -> bool {
+        self.covered_by_eval
+    }
+
     // Returns the position along the ray at parameter t (ray equation: origin + t*direction)
     pub fn at(&self, t: f64) -> point::Point3 {
         self.orig + t * self.dir
     }
-
-    pub fn color(&self) -> color::Color {
-        // Background gradient constants (must be local variables, not const)
-        let background_top_color = color::Color::new(0.5, 0.7, 1.0);
-        let background_bottom_color = color::Color::new(1.0, 1.0, 1.0);
-        let blend_factor_scale: f64 = 0.5;
-        let blend_factor_offset: f64 = 1.0;
-        let unit_direction = vector::unit_vector(self.direction());
-        let blend_factor = blend_factor_scale * (unit_direction.y() + blend_factor_offset);
-        background_bottom_color * (1.0 - blend_factor) + background_top_color * blend_factor
-    }
 }
@@ -0,0 +1,144 @@
+use crate::{color, material, microfacet, onb, ray, vector};
+
+// Microfacet dielectric (Walter et al. 2007) for frosted glass and rough plastics
+pub struct RoughDielectric {
+    refractive_index: f64,
+    alpha: f64,
+}
+
+impl RoughDielectric {
+    pub fn new(refractive_index: f64, roughness: f64) -> RoughDielectric {
+        RoughDielectric {
+            refractive_index,
+            alpha: microfacet::roughness_to_alpha(roughness),
+        }
+    }
+
+    pub fn scatter(
+        &self,
+        r_in: &ray::Ray,
+        rec: &crate::hit_record::HitRecord,
+        attenuation: &mut color::Color,
+        scattered: &mut ray::Ray,
+    ) -> bool {
+        // Incident index over transmitted index, so exiting rays use the inverse ratio
+        let eta = if rec.front_face() {
+            1.0 / self.refractive_index
+        } else {
+            self.refractive_index
+        };
+
+        // The stored normal always faces the incoming ray, so wo is in the upper hemisphere
+        let frame = onb::Onb::new(rec.normal());
+        let wo = frame.to_local(-vector::unit_vector(r_in.direction()));
+        if wo.z() <= 0.0 {
+            return false;
+        }
+
+        let Some((wi, weight)) = microfacet::sample_dielectric(&wo, self.alpha, eta) else {
+            return false;
+        };
+
+        *attenuation = color::Color::new(weight, weight, weight);
+        *scattered = ray::Ray::new(*rec.p(), frame.local(wi));
+        true
+    }
+
+    // Walter et al.'s reflection and transmission terms, scaled to match what scatter samples
+    pub fn eval(
+        &self,
+        r_in: &ray::Ray,
+        rec: &crate::hit_record::HitRecord,
+        direction: &vector::Vec3,
+    ) -> Option<color::Color> {
+        // Perfectly smooth glass only scatters along directions shadow rays never find
+        if self.alpha <= microfacet::MIN_ALPHA {
+            return None;
+        }
+        let eta = if rec.front_face() {
+            1.0 / self.refractive_index
+        } else {
+            self.refractive_index
+        };
+        let black = color::Color::new(0.0, 0.0, 0.0);
+        let frame = onb::Onb::new(rec.normal());
+        let wo = frame.to_local(-vector::unit_vector(r_in.direction()));
+        let wi = frame.to_local(vector::unit_vector(*direction));
+        if wo.z() <= 0.0 || wi.z() == 0.0 {
+            return Some(black);
+        }
+
+        if wi.z() > 0.0 {
+            let half = vector::unit_vector(wo + wi);
+            let fresnel = microfacet::fresnel_dielectric(vector::dot(wo, half), eta);
+            let value = fresnel * microfacet::eval_reflection(&wo, &wi, self.alpha);
+            return Some(color::Color::new(value, value, value));
+        }
+
+        // The microfacet normal that refracts wo into wi
+        let mut m = vector::unit_vector(-(eta * wo + wi));
+        if m.z() < 0.0 {
+            m = -m;
+        }
+        let cos_om = vector::dot(wo, m);
+        let cos_im = vector::dot(wi, m);
+        if cos_om <= 0.0 || cos_im >= 0.0 {
+            return Some(black);
+        }
+        let fresnel = microfacet::fresnel_dielectric(cos_om, eta);
+        let denom = eta * cos_om + cos_im;
+        let value = (1.0 - fresnel)
+            * microfacet::ggx_d(&m, self.alpha)
+            * microfacet::smith_g1(&wo, &m, self.alpha)
+            * microfacet::smith_g1(&wi, &m, self.alpha)
+            * cos_om
+            * -cos_im
+            / (wo.z() * denom * denom);
+        Some(color::Color::new(value, value, value))
+    }
+}
+
+impl material::Material for RoughDielectric {
+    fn scatter(
+        &self,
+        r_in: &crate::ray::Ray,
+        rec: &crate::hit_record::HitRecord,
+        attenuation: &mut color::Color,
+        scattered: &mut crate::ray::Ray,
+    ) -> bool {
+        self.scatter(r_in, rec, attenuation, scattered)
+    }
+
+    fn eval(
+        &self,
+        r_in: &crate::ray::Ray,
+        rec: &crate::hit_record::HitRecord,
+        direction: &vector::Vec3,
+    ) -> Option<color::Color> {
+        self.eval(r_in, rec, direction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::tests::{assert_close, scatter_and_eval, surface_hit};
+    use std::rc::Rc;
+
+    #[test]
+    fn white_furnace_from_both_sides() {
+        let material: Rc<dyn material::Material> = Rc::new(RoughDielectric::new(1.5, 0.9));
+        for front_face in [true, false] {
+            for wo in [vector::Vec3::new(0.0, 0.0, 1.0), vector::Vec3::new(0.6, 0.0, 0.8)] {
+                let (r_in, rec) = surface_hit(material.clone(), wo, front_face);
+                let (scattered, evaluated) = scatter_and_eval(&r_in, &rec, |_| 1.0);
+                assert_close(scattered, evaluated, 0.03);
+                assert!(scattered.x() <= 1.0);
+                // Reflected and transmitted shares separately
+                let (scattered, evaluated) =
+                    scatter_and_eval(&r_in, &rec, |wi| if wi.z() > 0.0 { 1.0 } else { 0.0 });
+                assert_close(scattered, evaluated, 0.03);
+            }
+        }
+    }
+}
@@ -0,0 +1,188 @@
+// Preetham, Shirley and Smits (1999) analytic daylight sky with a matching sun light.
+// Radiance is normalized so the zenith of the sky has a luminance of `intensity`.
+use crate::{color, light, spectrum, utils, vector};
+use std::f64::consts::PI;
+
+// Extraterrestrial solar illuminance in lux
+const SOLAR_ILLUMINANCE: f64 = 128_000.0;
+// Angular diameter of the sun seen from the earth, in degrees
+const SUN_ANGULAR_DIAMETER: f64 = 0.53;
+
+// Coefficients A..E of the Perez luminance distribution
+#[derive(Debug, Clone, Copy)]
+struct Perez([f64; 5]);
+
+impl Perez {
+    fn value(&self, theta: f64, gamma: f64) -> f64 {
+        let [a, b, c, d, e] = self.0;
+        let cos_gamma = gamma.cos();
+        (1.0 + a * (b / theta.cos()).exp())
+            * (1.0 + c * (d * gamma).exp() + e * cos_gamma * cos_gamma)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PreethamSky {
+    // Unit vector towards the sun
+    sun_direction: vector::Vec3,
+    sun_theta: f64,
+    turbidity: f64,
+    // Zenith luminance (kcd/m²) and chromaticity
+    zenith: (f64, f64, f64),
+    perez: [Perez; 3],
+    intensity: f64,
+}
+
+impl PreethamSky {
+    // Elevation is degrees above the horizon. Azimuth is degrees clockwise seen from above,
+    // with 0 towards -z and 90 towards +x. Turbidity ranges from about 2 (very clear) to 10 (hazy).
+    pub fn new(sun_elevation: f64, sun_azimuth: f64, turbidity: f64) -> PreethamSky {
+        let elevation = utils::degrees_to_radians(sun_elevation);
+        let azimuth = utils::degrees_to_radians(sun_azimuth);
+        let sun_direction = vector::Vec3::new(
+            elevation.cos() * azimuth.sin(),
+            elevation.sin(),
+            -elevation.cos() * azimuth.cos(),
+        );
+        let t = turbidity;
+        let theta = (PI / 2.0 - elevation).clamp(0.0, PI / 2.0);
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta);
+        let zenith_luminance = ((4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192).max(1e-3);
+        let (th, th2, th3) = (theta, theta * theta, theta * theta * theta);
+        let t2 = t * t;
+        let zenith_x = t2 * (0.00166 * th3 - 0.00375 * th2 + 0.00209 * th)
+            + t * (-0.02903 * th3 + 0.06377 * th2 - 0.03202 * th + 0.00394)
+            + (0.11693 * th3 - 0.21196 * th2 + 0.06052 * th + 0.25886);
+        let zenith_y = t2 * (0.00275 * th3 - 0.00610 * th2 + 0.00317 * th)
+            + t * (-0.04214 * th3 + 0.08970 * th2 - 0.04153 * th + 0.00516)
+            + (0.15346 * th3 - 0.26756 * th2 + 0.06670 * th + 0.26688);
+
+        let perez = [
+            Perez([
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ]),
+            Perez([
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ]),
+            Perez([
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ]),
+        ];
+
+        PreethamSky {
+            sun_direction,
+            sun_theta: theta,
+            turbidity,
+            zenith: (zenith_luminance, zenith_x, zenith_y),
+            perez,
+            intensity: 1.0,
+        }
+    }
+
+    // Scale the sky and sun together, relative to a zenith luminance of one
+    pub fn with_intensity(mut self, intensity: f64) -> PreethamSky {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn sun_direction(&self) -> vector::Vec3 {
+        self.sun_direction
+    }
+
+    // Linear sRGB radiance of the sky in `direction`, including the sun disk unless the
+    // path already gathered sunlight through sun_light. Below the horizon the horizon value
+    // is repeated, so scenes should provide their own ground.
+    pub fn radiance(&self, direction: &vector::Vec3, sun_sampled: bool) -> color::Color {
+        let direction = vector::unit_vector(*direction);
+        let sun = if sun_sampled {
+            color::Color::new(0.0, 0.0, 0.0)
+        } else {
+            self.sun_radiance(&direction)
+        };
+        let theta = direction.y().clamp(1e-3, 1.0).acos();
+        let gamma = vector::dot(direction, self.sun_direction).clamp(-1.0, 1.0).acos();
+
+        let (zenith_luminance, zenith_x, zenith_y) = self.zenith;
+        let relative = |perez: &Perez| perez.value(theta, gamma) / perez.value(0.0, self.sun_theta);
+        let luminance = zenith_luminance * relative(&self.perez[0]);
+        let x = zenith_x * relative(&self.perez[1]);
+        let y = zenith_y * relative(&self.perez[2]);
+
+        let scale = self.intensity / zenith_luminance;
+        let xyz = color::Color::new(x / y * luminance, luminance, (1.0 - x - y) / y * luminance);
+        spectrum::xyz_to_linear_srgb(xyz * scale) + sun
+    }
+
+    fn sun_cos_max() -> f64 {
+        utils::degrees_to_radians(SUN_ANGULAR_DIAMETER / 2.0).cos()
+    }
+
+    // The sun's irradiance spread evenly over the disk it covers
+    fn sun_radiance(&self, direction: &vector::Vec3) -> color::Color {
+        let cos_max = Self::sun_cos_max();
+        if vector::dot(*direction, self.sun_direction) < cos_max {
+            return color::Color::new(0.0, 0.0, 0.0);
+        }
+        self.sun_irradiance() / (2.0 * PI * (1.0 - cos_max))
+    }
+
+    // Directional light for the sun disk, in the same units as the sky radiance.
+    // Sunlight is reddened by Rayleigh and aerosol extinction along its path.
+    pub fn sun_light(&self) -> light::DirectionalLight {
+        let irradiance = self.sun_irradiance();
+        light::DirectionalLight::new(-self.sun_direction, irradiance, SUN_ANGULAR_DIAMETER)
+    }
+
+    fn sun_irradiance(&self) -> color::Color {
+        if self.sun_direction.y() <= 0.0 {
+            return color::Color::new(0.0, 0.0, 0.0);
+        }
+        let theta_degrees = self.sun_theta.to_degrees();
+        let air_mass = 1.0 / (self.sun_theta.cos() + 0.15 * (93.885 - theta_degrees).powf(-1.253));
+        let beta = 0.04608 * self.turbidity - 0.04586;
+        // Representative red, green and blue wavelengths in micrometres
+        let transmittance = |lambda: f64| {
+            let rayleigh = (-0.008735 * lambda.powf(-4.08) * air_mass).exp();
+            let aerosol = (-beta * lambda.powf(-1.3) * air_mass).exp();
+            rayleigh * aerosol
+        };
+        // Sky radiance is in kcd/m² before normalization, so convert lux to match
+        let scale = self.intensity * SOLAR_ILLUMINANCE / (self.zenith.0 * 1000.0);
+        color::Color::new(transmittance(0.65), transmittance(0.55), transmittance(0.45)) * scale
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{light::Light, point};
+
+    #[test]
+    fn sun_disk_matches_the_sun_light() {
+        let sky = PreethamSky::new(35.0, 120.0, 3.0).with_intensity(2.0);
+        let sun = sky.sun_direction();
+        let disk = sky.radiance(&sun, false) - sky.radiance(&sun, true);
+        let solid_angle = 2.0 * PI * (1.0 - PreethamSky::sun_cos_max());
+        let sample = sky.sun_light().sample(&point::Point3::new(0.0, 0.0, 0.0), None).unwrap();
+        let difference = disk * solid_angle - sample.radiance;
+        assert!(difference.length() < 1e-9 * sample.radiance.length());
+        assert!(sample.radiance.y() > 0.0);
+
+        // Away from the disk the sun adds nothing
+        let beside = vector::unit_vector(sun + vector::Vec3::new(0.0, 0.05, 0.0));
+        assert_eq!(sky.radiance(&beside, false), sky.radiance(&beside, true));
+    }
+}
@@ -0,0 +1,83 @@
+// Wavelength helpers for dispersion and spectral rendering. Wavelengths are in nanometres.
+use crate::{color, utils};
+use std::sync::OnceLock;
+
+pub const LAMBDA_MIN: f64 = 380.0;
+pub const LAMBDA_MAX: f64 = 780.0;
+// Sodium D line, the wavelength refractive indices are usually quoted at
+pub const LAMBDA_REFERENCE: f64 = 587.6;
+
+// Uniformly sample a visible wavelength; its pdf is 1 / (LAMBDA_MAX - LAMBDA_MIN)
+pub fn sample_wavelength() -> f64 {
+    utils::random_f64_in_range(LAMBDA_MIN, LAMBDA_MAX)
+}
+
+fn piecewise_gaussian(x: f64, mu: f64, sigma_low: f64, sigma_high: f64) -> f64 {
+    let sigma = if x < mu { sigma_low } else { sigma_high };
+    let t = (x - mu) / sigma;
+    (-0.5 * t * t).exp()
+}
+
+// CIE 1931 colour matching functions, using the multi-lobe fit of Wyman, Sloan and Shirley (2013)
+pub fn cie_xyz(lambda: f64) -> color::Color {
+    let x = 1.056 * piecewise_gaussian(lambda, 599.8, 37.9, 31.0)
+        + 0.362 * piecewise_gaussian(lambda, 442.0, 16.0, 26.7)
+        - 0.065 * piecewise_gaussian(lambda, 501.1, 20.4, 26.2);
+    let y = 0.821 * piecewise_gaussian(lambda, 568.8, 46.9, 40.5)
+        + 0.286 * piecewise_gaussian(lambda, 530.9, 16.3, 31.1);
+    let z = 1.217 * piecewise_gaussian(lambda, 437.0, 11.8, 36.0)
+        + 0.681 * piecewise_gaussian(lambda, 459.0, 26.0, 13.8);
+    color::Color::new(x, y, z)
+}
+
+pub fn xyz_to_linear_srgb(xyz: color::Color) -> color::Color {
+    color::Color::new(
+        3.2404542 * xyz.x() - 1.5371385 * xyz.y() - 0.4985314 * xyz.z(),
+        -0.9692660 * xyz.x() + 1.8760108 * xyz.y() + 0.0415560 * xyz.z(),
+        0.0556434 * xyz.x() - 0.2040259 * xyz.y() + 1.0572252 * xyz.z(),
+    )
+}
+
+// Per-channel scale that makes a flat spectrum come out white
+fn white_balance() -> color::Color {
+    static WHITE_BALANCE: OnceLock<color::Color> = OnceLock::new();
+    *WHITE_BALANCE.get_or_init(|| {
+        let steps = 400;
+        let step = (LAMBDA_MAX - LAMBDA_MIN) / steps as f64;
+        let mut sum = color::Color::new(0.0, 0.0, 0.0);
+        for i in 0..steps {
+            let lambda = LAMBDA_MIN + (i as f64 + 0.5) * step;
+            sum += xyz_to_linear_srgb(cie_xyz(lambda));
+        }
+        let mean = sum / steps as f64;
+        color::Color::new(1.0 / mean.x(), 1.0 / mean.y(), 1.0 / mean.z())
+    })
+}
+
+// Convert XYZ accumulated from uniformly sampled wavelengths to linear sRGB,
+// white balanced so that a flat spectrum comes out white
+pub fn xyz_to_rgb(xyz: color::Color) -> color::Color {
+    xyz_to_linear_srgb(xyz) * white_balance()
+}
+
+// Linear sRGB response to a single wavelength, scaled so that the average over
+// uniformly sampled wavelengths is white. Components can be negative for
+// saturated wavelengths outside the sRGB gamut.
+pub fn wavelength_to_rgb(lambda: f64) -> color::Color {
+    xyz_to_rgb(cie_xyz(lambda))
+}
+
+// Upsample an RGB reflectance or radiance to a spectrum and evaluate it at `lambda`.
+// The spectrum is a blend of three smooth basis curves that sum to one at every
+// wavelength, so white maps to a flat spectrum and albedos in [0, 1] stay in [0, 1].
+pub fn rgb_to_spectrum(rgb: color::Color, lambda: f64) -> f64 {
+    let response = wavelength_to_rgb(lambda);
+    let r = response.x().max(0.0);
+    let g = response.y().max(0.0);
+    let b = response.z().max(0.0);
+    let total = r + g + b;
+    if total <= 0.0 {
+        return (rgb.x() + rgb.y() + rgb.z()) / 3.0;
+    }
+    (rgb.x() * r + rgb.y() * g + rgb.z() * b) / total
+}
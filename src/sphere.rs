@@ -1,4 +1,4 @@
-use crate::{hit_record, hittable, interval, material, ray, vector};
+use crate::{hit_record, hittable, interval, material, onb, ray, vector};
 use std::rc;
 
 pub struct Sphere {
@@ -39,6 +39,7 @@ pub fn add_sphere(world: &mut crate::hittable_list::HittableList, spec: &SphereS
 impl hittable::Hittable for Sphere {
     // Ray-sphere intersection using the quadratic equation
     fn hit(&self, r: &ray::Ray, ray_t: &interval::Interval) -> Option<hit_record::HitRecord> {
+        hittable::count_hit_call();
         let oc = self.center - r.origin();
         let a = r.direction().length_squared();
         let h = vector::dot(r.direction(), oc);
@@ -60,13 +61,46 @@ impl hittable::Hittable for Sphere {
         let p = r.at(t);
         let outward_normal = (p - self.center) / self.radius;
         let (front_face, normal) = hit_record::face_normal(r, &outward_normal);
+        let (u, v) = sphere_uv(&outward_normal);
+        // Direction of increasing u around the Y axis, undefined at the poles
+        let around = vector::Vec3::new(outward_normal.z(), 0.0, -outward_normal.x());
+        let tangent = if around.near_zero() {
+            onb::Onb::new(&outward_normal).u()
+        } else {
+            vector::unit_vector(around)
+        };
+        // u sweeps 2 pi radians around the Y axis and v sweeps pi radians from pole to pole
+        let pi = std::f64::consts::PI;
+        let dpdu = around * (2.0 * pi * self.radius);
+        let dpdv = vector::cross(outward_normal, tangent) * (pi * self.radius);
 
         Some(hit_record::HitRecord {
             p,
             normal,
+            geometric_normal: normal,
             t,
             front_face,
             material: self.material.clone(),
+            u,
+            v,
+            tangent,
+            dpdu,
+            dpdv,
         })
     }
+
+    fn materials(&self) -> Vec<rc::Rc<dyn material::Material>> {
+        vec![self.material.clone()]
+    }
+}
+
+// Map a point on the unit sphere to (u, v): u is the angle around the Y axis from X = -1,
+// v is the angle from Y = -1 up to Y = +1, both scaled to [0, 1]
+fn sphere_uv(p: &vector::Vec3) -> (f64, f64) {
+    let theta = (-p.y()).acos();
+    let phi = (-p.z()).atan2(p.x()) + std::f64::consts::PI;
+    (
+        phi / (2.0 * std::f64::consts::PI),
+        theta / std::f64::consts::PI,
+    )
 }
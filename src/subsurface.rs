@@ -0,0 +1,159 @@
+use crate::{color, hit_record, material, microfacet, ray, utils, vector};
+
+// Subsurface scattering for skin, wax, marble and milk. Light refracts through a smooth
+// dielectric boundary and then random-walks inside the closed object it is applied to.
+pub struct Subsurface {
+    refractive_index: f64,
+    // Probability of a collision inside the medium scattering rather than absorbing, per channel
+    albedo: color::Color,
+    // Extinction coefficient per channel, the inverse of the mean free path
+    extinction: color::Color,
+}
+
+fn channel(color: &color::Color, index: usize) -> f64 {
+    match index {
+        0 => color.x(),
+        1 => color.y(),
+        _ => color.z(),
+    }
+}
+
+fn exp(color: color::Color) -> color::Color {
+    color::Color::new(color.x().exp(), color.y().exp(), color.z().exp())
+}
+
+fn mean(color: color::Color) -> f64 {
+    (color.x() + color.y() + color.z()) / 3.0
+}
+
+impl Subsurface {
+    pub fn new(refractive_index: f64, albedo: color::Color, mean_free_path: f64) -> Subsurface {
+        Subsurface {
+            refractive_index,
+            albedo,
+            extinction: color::Color::new(1.0, 1.0, 1.0) / mean_free_path,
+        }
+    }
+
+    // From per-channel scattering and absorption coefficients, so each channel keeps its own
+    // mean free path and, for example, red light can travel deeper into skin than blue.
+    pub fn from_coefficients(
+        refractive_index: f64,
+        scattering: color::Color,
+        absorption: color::Color,
+    ) -> Subsurface {
+        let extinction = scattering + absorption;
+        let ratio = |s: f64, t: f64| if t > 0.0 { s / t } else { 1.0 };
+        Subsurface {
+            refractive_index,
+            albedo: color::Color::new(
+                ratio(scattering.x(), extinction.x()),
+                ratio(scattering.y(), extinction.y()),
+                ratio(scattering.z(), extinction.z()),
+            ),
+            extinction,
+        }
+    }
+
+    pub fn scatter(
+        &self,
+        r_in: &ray::Ray,
+        rec: &hit_record::HitRecord,
+        attenuation: &mut color::Color,
+        scattered: &mut ray::Ray,
+    ) -> bool {
+        let mut transmittance = color::Color::new(1.0, 1.0, 1.0);
+        if !rec.front_face() {
+            // The ray travelled through the medium to reach this back face. A free-flight
+            // distance is sampled with the extinction of one randomly chosen channel and every
+            // channel is weighted by its own extinction over the average pdf of all three
+            // (spectral MIS). If the distance is shorter than the segment, the ray collided
+            // inside and scatters isotropically.
+            let segment = rec.t() * r_in.direction().length();
+            let hero = ((utils::random_f64() * 3.0) as usize).min(2);
+            let free_flight = -(1.0 - utils::random_f64()).ln() / channel(&self.extinction, hero);
+            if free_flight < segment {
+                let decay = exp(-self.extinction * free_flight);
+                let pdf = mean(self.extinction * decay);
+                if pdf <= 0.0 {
+                    return false;
+                }
+                let collision = r_in.at(free_flight / r_in.direction().length());
+                *attenuation = self.albedo * self.extinction * decay / pdf;
+                *scattered = ray::Ray::new(collision, vector::random_unit_vector());
+                return true;
+            }
+            let decay = exp(-self.extinction * segment);
+            let pdf = mean(decay);
+            if pdf <= 0.0 {
+                return false;
+            }
+            transmittance = decay / pdf;
+        }
+
+        // Smooth dielectric boundary, entering or leaving the medium
+        let eta = if rec.front_face() {
+            1.0 / self.refractive_index
+        } else {
+            self.refractive_index
+        };
+        let unit_direction = vector::unit_vector(r_in.direction());
+        let cos_theta = vector::dot(-unit_direction, *rec.normal());
+        let direction = if utils::random_f64() < microfacet::fresnel_dielectric(cos_theta, eta) {
+            vector::reflect(&unit_direction, rec.normal())
+        } else {
+            vector::refract(&unit_direction, rec.normal(), eta)
+        };
+        *attenuation = transmittance;
+        *scattered = ray::Ray::new(*rec.p(), direction);
+        true
+    }
+}
+
+impl material::Material for Subsurface {
+    fn scatter(
+        &self,
+        r_in: &ray::Ray,
+        rec: &hit_record::HitRecord,
+        attenuation: &mut color::Color,
+        scattered: &mut ray::Ray,
+    ) -> bool {
+        self.scatter(r_in, rec, attenuation, scattered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::tests::{assert_close, surface_hit};
+    use crate::point;
+    use std::rc::Rc;
+
+    #[test]
+    fn spectral_free_flight_is_unbiased_per_channel() {
+        let scattering = color::Color::new(0.5, 1.0, 2.0);
+        let absorption = color::Color::new(0.5, 0.2, 0.1);
+        let material = Rc::new(Subsurface::from_coefficients(1.3, scattering, absorption));
+        // A back face one unit along the ray, reached from inside the medium
+        let (r_in, rec) = surface_hit(material.clone(), vector::Vec3::new(0.0, 0.0, 1.0), false);
+
+        let samples = 200_000;
+        let black = color::Color::new(0.0, 0.0, 0.0);
+        let (mut collided, mut escaped) = (black, black);
+        for _ in 0..samples {
+            let mut attenuation = black;
+            let mut scattered = r_in;
+            assert!(material.scatter(&r_in, &rec, &mut attenuation, &mut scattered));
+            if scattered.origin() == point::Point3::new(0.0, 0.0, 0.0) {
+                escaped += attenuation;
+            } else {
+                collided += attenuation;
+            }
+        }
+        let extinction = scattering + absorption;
+        let survival = exp(-extinction);
+        assert_close(escaped / samples as f64, survival, 0.01);
+        let white = color::Color::new(1.0, 1.0, 1.0);
+        assert_close(collided / samples as f64, (white - survival) * material.albedo, 0.01);
+    }
+}
@@ -0,0 +1,220 @@
+use crate::{color, point};
+use std::sync::Arc;
+
+pub trait Texture: Send + Sync {
+    // Colour at surface coordinates (u, v), or at point p for solid textures
+    fn value(&self, u: f64, v: f64, p: &point::Point3) -> color::Color;
+}
+
+pub struct SolidColor {
+    albedo: color::Color,
+}
+
+impl SolidColor {
+    pub fn new(albedo: color::Color) -> SolidColor {
+        SolidColor { albedo }
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f64, _v: f64, _p: &point::Point3) -> color::Color {
+        self.albedo
+    }
+}
+
+// 3D checker pattern alternating between two textures every `scale` units
+pub struct CheckerTexture {
+    inv_scale: f64,
+    even: Arc<dyn Texture>,
+    odd: Arc<dyn Texture>,
+}
+
+impl CheckerTexture {
+    pub fn new(scale: f64, even: Arc<dyn Texture>, odd: Arc<dyn Texture>) -> CheckerTexture {
+        CheckerTexture {
+            inv_scale: 1.0 / scale,
+            even,
+            odd,
+        }
+    }
+}
+
+impl Texture for CheckerTexture {
+    fn value(&self, u: f64, v: f64, p: &point::Point3) -> color::Color {
+        let x = (self.inv_scale * p.x()).floor() as i64;
+        let y = (self.inv_scale * p.y()).floor() as i64;
+        let z = (self.inv_scale * p.z()).floor() as i64;
+        if (x + y + z) % 2 == 0 {
+            self.even.value(u, v, p)
+        } else {
+            self.odd.value(u, v, p)
+        }
+    }
+}
+
+// Texture backed by a PPM image (P3 or P6), sampled with nearest-neighbour lookup.
+// Values are returned as stored, so data such as normal maps is not gamma decoded.
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    pixels: Vec<color::Color>,
+}
+
+impl ImageTexture {
+    pub fn load(path: &str) -> std::io::Result<ImageTexture> {
+        Self::from_ppm(&std::fs::read(path)?)
+    }
+
+    pub fn from_ppm(bytes: &[u8]) -> std::io::Result<ImageTexture> {
+        let invalid =
+            |message: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, message.to_string());
+
+        // Header fields are whitespace separated and may be interleaved with # comments
+        let mut pos = 0;
+        let next_token = |pos: &mut usize| -> Option<String> {
+            loop {
+                while *pos < bytes.len() && bytes[*pos].is_ascii_whitespace() {
+                    *pos += 1;
+                }
+                if *pos < bytes.len() && bytes[*pos] == b'#' {
+                    while *pos < bytes.len() && bytes[*pos] != b'\n' {
+                        *pos += 1;
+                    }
+                    continue;
+                }
+                break;
+            }
+            let start = *pos;
+            while *pos < bytes.len() && !bytes[*pos].is_ascii_whitespace() {
+                *pos += 1;
+            }
+            (start < *pos).then(|| String::from_utf8_lossy(&bytes[start..*pos]).into_owned())
+        };
+        let header_number = |pos: &mut usize| -> std::io::Result<usize> {
+            next_token(pos)
+                .and_then(|token| token.parse().ok())
+                .ok_or_else(|| invalid("malformed PPM header"))
+        };
+
+        let magic = next_token(&mut pos).ok_or_else(|| invalid("empty PPM file"))?;
+        let width = header_number(&mut pos)?;
+        let height = header_number(&mut pos)?;
+        let max_value = header_number(&mut pos)?;
+        if width == 0 || height == 0 || max_value == 0 || max_value > 65535 {
+            return Err(invalid("unsupported PPM dimensions"));
+        }
+        let count = width
+            .checked_mul(height)
+            .and_then(|pixels| pixels.checked_mul(3))
+            .ok_or_else(|| invalid("PPM dimensions too large"))?;
+
+        let samples: Vec<usize> = match magic.as_str() {
+            "P3" => (0..count)
+                .map(|_| header_number(&mut pos))
+                .collect::<std::io::Result<_>>()?,
+            "P6" => {
+                // A single whitespace byte separates the header from the raster
+                let data = &bytes[(pos + 1).min(bytes.len())..];
+                if max_value < 256 {
+                    data.iter().take(count).map(|&b| b as usize).collect()
+                } else {
+                    data.chunks_exact(2)
+                        .take(count)
+                        .map(|pair| ((pair[0] as usize) << 8) | pair[1] as usize)
+                        .collect()
+                }
+            }
+            _ => return Err(invalid("only P3 and P6 PPM images are supported")),
+        };
+        if samples.len() != count {
+            return Err(invalid("truncated PPM raster"));
+        }
+        if samples.iter().any(|&sample| sample > max_value) {
+            return Err(invalid("PPM sample above the maximum value"));
+        }
+
+        let scale = 1.0 / max_value as f64;
+        let pixels = samples
+            .chunks_exact(3)
+            .map(|rgb| {
+                color::Color::new(
+                    rgb[0] as f64 * scale,
+                    rgb[1] as f64 * scale,
+                    rgb[2] as f64 * scale,
+                )
+            })
+            .collect();
+        Ok(ImageTexture {
+            width,
+            height,
+            pixels,
+        })
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _p: &point::Point3) -> color::Color {
+        // Wrap u and flip v, since image rows run top to bottom
+        let u = u - u.floor();
+        let v = 1.0 - (v - v.floor());
+        let i = ((u * self.width as f64) as usize).min(self.width - 1);
+        let j = ((v * self.height as f64) as usize).min(self.height - 1);
+        self.pixels[j * self.width + i]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_invalid_data(result: std::io::Result<ImageTexture>) -> bool {
+        matches!(result, Err(error) if error.kind() == std::io::ErrorKind::InvalidData)
+    }
+
+    #[test]
+    fn ascii_ppm_with_comments() {
+        let texture =
+            ImageTexture::from_ppm(b"P3\n# a comment\n2 1\n255\n255 0 0  0 0 255\n").unwrap();
+        assert_eq!((texture.width, texture.height), (2, 1));
+        let p = point::Point3::new(0.0, 0.0, 0.0);
+        assert_eq!(texture.value(0.25, 0.5, &p), color::Color::new(1.0, 0.0, 0.0));
+        assert_eq!(texture.value(0.75, 0.5, &p), color::Color::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn binary_ppm_rows_run_top_to_bottom() {
+        let mut bytes = b"P6 1 2 255\n".to_vec();
+        bytes.extend_from_slice(&[255, 255, 255, 0, 0, 0]);
+        let texture = ImageTexture::from_ppm(&bytes).unwrap();
+        let p = point::Point3::new(0.0, 0.0, 0.0);
+        assert_eq!(texture.value(0.5, 0.75, &p), color::Color::new(1.0, 1.0, 1.0));
+        assert_eq!(texture.value(0.5, 0.25, &p), color::Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn sixteen_bit_binary_ppm() {
+        let mut bytes = b"P6 1 1 65535\n".to_vec();
+        bytes.extend_from_slice(&[0xff, 0xff, 0x00, 0x00, 0x80, 0x00]);
+        let texture = ImageTexture::from_ppm(&bytes).unwrap();
+        let rgb = texture.pixels[0];
+        assert_eq!((rgb.x(), rgb.y()), (1.0, 0.0));
+        assert!((rgb.z() - 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn malformed_ppms_are_rejected() {
+        assert!(is_invalid_data(ImageTexture::from_ppm(b"")));
+        assert!(is_invalid_data(ImageTexture::from_ppm(b"P5 1 1 255\n\0")));
+        assert!(is_invalid_data(ImageTexture::from_ppm(b"P3 2 x 255\n")));
+        assert!(is_invalid_data(ImageTexture::from_ppm(b"P3 0 1 255\n")));
+        assert!(is_invalid_data(ImageTexture::from_ppm(b"P3 1 1 255\n1 2\n")));
+        assert!(is_invalid_data(ImageTexture::from_ppm(b"P6 2 2 255\n\0\0\0")));
+        // Dimensions whose product overflows must not wrap around to a small raster
+        let huge = format!("P6 {} {} 255\n", usize::MAX / 2, 3);
+        assert!(is_invalid_data(ImageTexture::from_ppm(huge.as_bytes())));
+        // Samples above the maximum value would give texels brighter than white
+        assert!(is_invalid_data(ImageTexture::from_ppm(b"P3 1 1 255\n300 0 0\n")));
+        assert!(is_invalid_data(ImageTexture::from_ppm(b"P6 1 1 100\n\x65\0\0")));
+        assert!(ImageTexture::from_ppm(b"P3 1 1 255\n255 0 0\n").is_ok());
+    }
+}
@@ -0,0 +1,214 @@
+// Thin-film interference (iridescence) for soap bubbles, oil slicks and anodized metal.
+// Reflectance follows the Airy summation over a single film between two media.
+use crate::{color, spectrum};
+use std::f64::consts::PI;
+
+// Wavelengths used when integrating the film reflectance down to RGB
+const RGB_WAVELENGTH_SAMPLES: usize = 16;
+
+#[derive(Debug, Clone, Copy)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Complex {
+    fn new(re: f64, im: f64) -> Complex {
+        Complex { re, im }
+    }
+
+    fn real(re: f64) -> Complex {
+        Complex { re, im: 0.0 }
+    }
+
+    fn add(self, rhs: Complex) -> Complex {
+        Complex::new(self.re + rhs.re, self.im + rhs.im)
+    }
+
+    fn sub(self, rhs: Complex) -> Complex {
+        Complex::new(self.re - rhs.re, self.im - rhs.im)
+    }
+
+    fn mul(self, rhs: Complex) -> Complex {
+        Complex::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+
+    fn div(self, rhs: Complex) -> Complex {
+        let denom = rhs.norm_sqr();
+        Complex::new(
+            (self.re * rhs.re + self.im * rhs.im) / denom,
+            (self.im * rhs.re - self.re * rhs.im) / denom,
+        )
+    }
+
+    fn norm_sqr(self) -> f64 {
+        self.re * self.re + self.im * self.im
+    }
+
+    fn sqrt(self) -> Complex {
+        let modulus = self.norm_sqr().sqrt();
+        let re = (0.5 * (modulus + self.re)).max(0.0).sqrt();
+        let im = (0.5 * (modulus - self.re)).max(0.0).sqrt();
+        Complex::new(re, if self.im < 0.0 { -im } else { im })
+    }
+
+    // e^(i * self)
+    fn exp_i(self) -> Complex {
+        let scale = (-self.im).exp();
+        Complex::new(scale * self.re.cos(), scale * self.re.sin())
+    }
+}
+
+// Amplitude reflection coefficients (s, p) between media a and b
+fn fresnel_amplitudes(
+    n_a: Complex,
+    cos_a: Complex,
+    n_b: Complex,
+    cos_b: Complex,
+) -> (Complex, Complex) {
+    let r_s = n_a.mul(cos_a).sub(n_b.mul(cos_b)).div(n_a.mul(cos_a).add(n_b.mul(cos_b)));
+    let r_p = n_b.mul(cos_a).sub(n_a.mul(cos_b)).div(n_b.mul(cos_a).add(n_a.mul(cos_b)));
+    (r_s, r_p)
+}
+
+// Complex index of a conductor with the given normal-incidence reflectance, using
+// Gulbrandsen's artist-friendly mapping with the edge tint equal to the reflectance
+fn conductor_index(reflectance: f64) -> Complex {
+    let r = reflectance.clamp(0.0, 0.99);
+    let g = r;
+    let sqrt_r = r.sqrt();
+    let n = g * (1.0 - r) / (1.0 + r) + (1.0 - g) * (1.0 + sqrt_r) / (1.0 - sqrt_r);
+    let k2 = (r * (n + 1.0) * (n + 1.0) - (n - 1.0) * (n - 1.0)) / (1.0 - r);
+    Complex::new(n, k2.max(0.0).sqrt())
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ThinFilm {
+    // Film thickness in nanometres
+    thickness: f64,
+    refractive_index: f64,
+}
+
+impl ThinFilm {
+    pub fn new(thickness: f64, refractive_index: f64) -> ThinFilm {
+        ThinFilm {
+            thickness,
+            refractive_index,
+        }
+    }
+
+    // Reflectance of outer medium / film / substrate at one wavelength, for unpolarized light
+    fn airy_reflectance(&self, cos_i: f64, outer: f64, substrate: Complex, wavelength: f64) -> f64 {
+        let n1 = Complex::real(outer);
+        let n2 = Complex::real(self.refractive_index);
+        let cos1 = Complex::real(cos_i.clamp(0.0, 1.0));
+        // Snell's law carries n1 sin(theta1) through every layer
+        let invariant = Complex::real(outer * outer * (1.0 - cos_i * cos_i));
+        let one = Complex::real(1.0);
+        let cos2 = one.sub(invariant.div(n2.mul(n2))).sqrt();
+        let cos3 = one.sub(invariant.div(substrate.mul(substrate))).sqrt();
+
+        let (r12_s, r12_p) = fresnel_amplitudes(n1, cos1, n2, cos2);
+        let (r23_s, r23_p) = fresnel_amplitudes(n2, cos2, substrate, cos3);
+
+        // Phase difference between successive reflections inside the film
+        let phase = n2
+            .mul(cos2)
+            .mul(Complex::real(4.0 * PI * self.thickness / wavelength))
+            .exp_i();
+        let airy = |r12: Complex, r23: Complex| {
+            let numerator = r12.add(r23.mul(phase));
+            let denominator = one.add(r12.mul(r23).mul(phase));
+            numerator.div(denominator).norm_sqr()
+        };
+        (0.5 * (airy(r12_s, r23_s) + airy(r12_p, r23_p))).clamp(0.0, 1.0)
+    }
+
+    // Average the film reflectance over the visible range with each channel's spectral response
+    fn reflectance_rgb<F: Fn(usize) -> Complex>(
+        &self,
+        cos_i: f64,
+        outer: f64,
+        substrate: F,
+    ) -> color::Color {
+        let mut reflected = [0.0; 3];
+        let mut response = [0.0; 3];
+        let step = (spectrum::LAMBDA_MAX - spectrum::LAMBDA_MIN) / RGB_WAVELENGTH_SAMPLES as f64;
+        for i in 0..RGB_WAVELENGTH_SAMPLES {
+            let lambda = spectrum::LAMBDA_MIN + (i as f64 + 0.5) * step;
+            let rgb = spectrum::wavelength_to_rgb(lambda);
+            for (channel, weight) in [rgb.x(), rgb.y(), rgb.z()].into_iter().enumerate() {
+                reflected[channel] +=
+                    weight * self.airy_reflectance(cos_i, outer, substrate(channel), lambda);
+                response[channel] += weight;
+            }
+        }
+        let interval = crate::interval::Interval::new(0.0, 1.0);
+        color::Color::new(
+            interval.clamp(reflected[0] / response[0]),
+            interval.clamp(reflected[1] / response[1]),
+            interval.clamp(reflected[2] / response[2]),
+        )
+    }
+
+    // Reflectance of a film over a dielectric at a single wavelength
+    pub fn dielectric_reflectance(&self, cos_i: f64, outer: f64, substrate: f64, wavelength: f64) -> f64 {
+        self.airy_reflectance(cos_i, outer, Complex::real(substrate), wavelength)
+    }
+
+    pub fn dielectric_reflectance_rgb(&self, cos_i: f64, outer: f64, substrate: f64) -> color::Color {
+        self.reflectance_rgb(cos_i, outer, |_| Complex::real(substrate))
+    }
+
+    // Reflectance of a film over a conductor whose bare normal-incidence reflectance is `albedo`
+    pub fn conductor_reflectance(&self, cos_i: f64, outer: f64, albedo: f64, wavelength: f64) -> f64 {
+        self.airy_reflectance(cos_i, outer, conductor_index(albedo), wavelength)
+    }
+
+    pub fn conductor_reflectance_rgb(&self, cos_i: f64, outer: f64, albedo: color::Color) -> color::Color {
+        let indices = [
+            conductor_index(albedo.x()),
+            conductor_index(albedo.y()),
+            conductor_index(albedo.z()),
+        ];
+        self.reflectance_rgb(cos_i, outer, |channel| indices[channel])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::microfacet;
+
+    #[test]
+    fn film_of_zero_thickness_is_plain_fresnel() {
+        let film = ThinFilm::new(0.0, 1.33);
+        for cos_i in [1.0, 0.8, 0.5, 0.2, 0.05] {
+            for (outer, substrate) in [(1.0, 1.5), (1.5, 1.0)] {
+                let expected = microfacet::fresnel_dielectric(cos_i, outer / substrate);
+                let actual = film.dielectric_reflectance(cos_i, outer, substrate, 550.0);
+                assert!((actual - expected).abs() < 1e-9, "{actual} != {expected}");
+            }
+        }
+    }
+
+    #[test]
+    fn film_of_zero_thickness_keeps_the_conductor_reflectance_head_on() {
+        let film = ThinFilm::new(0.0, 1.5);
+        for albedo in [0.2, 0.6, 0.95] {
+            assert!((film.conductor_reflectance(1.0, 1.0, albedo, 550.0) - albedo).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn quarter_wave_film_cancels_reflection() {
+        // The classic single-layer anti-reflection coating on glass
+        let index = 1.5_f64.sqrt();
+        let film = ThinFilm::new(550.0 / (4.0 * index), index);
+        assert!(film.dielectric_reflectance(1.0, 1.0, 1.5, 550.0) < 1e-9);
+        assert!(film.dielectric_reflectance(1.0, 1.0, 1.5, 400.0) > 1e-3);
+    }
+}
@@ -154,8 +154,16 @@ pub fn random_in_range(min: f64, max: f64) -> Vec3 {
     }
 }
 
+// Direction distributed uniformly over the whole unit sphere
 pub fn random_unit_vector() -> Vec3 {
-    unit_vector(random())
+    let z = 1.0 - 2.0 * utils::random_f64();
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * std::f64::consts::PI * utils::random_f64();
+    Vec3 {
+        x: r * phi.cos(),
+        y: r * phi.sin(),
+        z,
+    }
 }
 
 pub fn random_on_hemisphere(normal: Vec3) -> Vec3 {
@@ -176,6 +184,19 @@ pub fn random_in_unit_sphere() -> Vec3 {
     }
 }
 
+// Cosine-weighted direction about the local z axis, with pdf cos(theta) / pi
+pub fn random_cosine_direction() -> Vec3 {
+    let r1 = utils::random_f64();
+    let r2 = utils::random_f64();
+    let phi = 2.0 * std::f64::consts::PI * r1;
+    let r = r2.sqrt();
+    Vec3 {
+        x: phi.cos() * r,
+        y: phi.sin() * r,
+        z: (1.0 - r2).sqrt(),
+    }
+}
+
 // Reflect vector v around normal n
 pub fn reflect(v: &Vec3, n: &Vec3) -> Vec3 {
     *v - 2.0 * dot(*v, *n) * *n
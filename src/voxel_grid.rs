@@ -0,0 +1,227 @@
+// Regular 3D grid of scalar values (density, temperature, ...) over an axis-aligned box.
+//
+// Raw file layout, all little-endian:
+//   3 x u32  grid resolution nx, ny, nz
+//   6 x f32  bounds: min x, y, z then max x, y, z
+//   nx * ny * nz x f32  values, x varying fastest, then y, then z
+use crate::{aabb, point};
+use std::io;
+
+pub struct VoxelGrid {
+    nx: usize,
+    ny: usize,
+    nz: usize,
+    bounds: aabb::Aabb,
+    values: Vec<f32>,
+    max_value: f64,
+}
+
+// Number of voxels in a grid, or None if it doesn't fit in memory addressing
+fn voxel_count(nx: usize, ny: usize, nz: usize) -> Option<usize> {
+    nx.checked_mul(ny)?.checked_mul(nz)
+}
+
+impl VoxelGrid {
+    pub fn new(
+        resolution: (usize, usize, usize),
+        min: point::Point3,
+        max: point::Point3,
+        values: Vec<f32>,
+    ) -> io::Result<VoxelGrid> {
+        let (nx, ny, nz) = resolution;
+        if nx == 0 || ny == 0 || nz == 0 || voxel_count(nx, ny, nz) != Some(values.len()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "voxel count does not match the grid resolution",
+            ));
+        }
+        // Every axis needs a positive, finite extent for lookups to map into the grid
+        let extents = [(min.x(), max.x()), (min.y(), max.y()), (min.z(), max.z())];
+        if extents.iter().any(|&(lo, hi)| !(lo.is_finite() && hi.is_finite() && lo < hi)) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "voxel grid bounds are empty, inverted or not finite",
+            ));
+        }
+        // Densities and the like can't be negative, and one NaN would spread over the image
+        if values.iter().any(|v| !v.is_finite() || *v < 0.0) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "voxel values must be finite and not negative",
+            ));
+        }
+        let max_value = values.iter().fold(0.0_f64, |m, &v| m.max(v as f64));
+        Ok(VoxelGrid {
+            nx,
+            ny,
+            nz,
+            bounds: aabb::Aabb::new(min, max),
+            values,
+            max_value,
+        })
+    }
+
+    pub fn load(path: &str) -> io::Result<VoxelGrid> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<VoxelGrid> {
+        let truncated = || io::Error::new(io::ErrorKind::UnexpectedEof, "truncated voxel grid");
+        let word = |index: usize| -> io::Result<[u8; 4]> {
+            bytes
+                .get(index * 4..index * 4 + 4)
+                .map(|b| [b[0], b[1], b[2], b[3]])
+                .ok_or_else(truncated)
+        };
+        let nx = u32::from_le_bytes(word(0)?) as usize;
+        let ny = u32::from_le_bytes(word(1)?) as usize;
+        let nz = u32::from_le_bytes(word(2)?) as usize;
+        let float =
+            |index: usize| -> io::Result<f64> { Ok(f32::from_le_bytes(word(index)?) as f64) };
+        let min = point::Point3::new(float(3)?, float(4)?, float(5)?);
+        let max = point::Point3::new(float(6)?, float(7)?, float(8)?);
+
+        let count = voxel_count(nx, ny, nz).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, "voxel grid resolution too large")
+        })?;
+        // Check the file holds every value before reading, so a bogus header can't run far
+        let needed = count.checked_add(9).and_then(|words| words.checked_mul(4));
+        if needed.is_none_or(|needed| needed > bytes.len()) {
+            return Err(truncated());
+        }
+        let values = (0..count)
+            .map(|i| word(9 + i).map(f32::from_le_bytes))
+            .collect::<io::Result<Vec<f32>>>()?;
+        Self::new((nx, ny, nz), min, max, values)
+    }
+
+    pub fn bounds(&self) -> &aabb::Aabb {
+        &self.bounds
+    }
+
+    // Largest value in the grid, an upper bound for any interpolated lookup
+    pub fn max_value(&self) -> f64 {
+        self.max_value
+    }
+
+    fn voxel(&self, x: usize, y: usize, z: usize) -> f64 {
+        self.values[(z * self.ny + y) * self.nx + x] as f64
+    }
+
+    // Trilinearly interpolated value at a world-space point; zero outside the bounds
+    pub fn value(&self, p: &point::Point3) -> f64 {
+        let min = self.bounds.min();
+        let max = self.bounds.max();
+        let local = [
+            (p.x() - min.x()) / (max.x() - min.x()),
+            (p.y() - min.y()) / (max.y() - min.y()),
+            (p.z() - min.z()) / (max.z() - min.z()),
+        ];
+        if local.iter().any(|c| !(0.0..=1.0).contains(c)) {
+            return 0.0;
+        }
+
+        // Voxel centres sit at (i + 0.5) / n
+        let mut cell = [0usize; 3];
+        let mut frac = [0.0; 3];
+        for (axis, n) in [self.nx, self.ny, self.nz].into_iter().enumerate() {
+            let coord = (local[axis] * n as f64 - 0.5).clamp(0.0, (n - 1) as f64);
+            cell[axis] = (coord.floor() as usize).min(n.saturating_sub(2));
+            frac[axis] = coord - cell[axis] as f64;
+        }
+        let next = |axis: usize, n: usize| (cell[axis] + 1).min(n - 1);
+        let (x0, y0, z0) = (cell[0], cell[1], cell[2]);
+        let (x1, y1, z1) = (next(0, self.nx), next(1, self.ny), next(2, self.nz));
+        let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+
+        let c00 = lerp(self.voxel(x0, y0, z0), self.voxel(x1, y0, z0), frac[0]);
+        let c10 = lerp(self.voxel(x0, y1, z0), self.voxel(x1, y1, z0), frac[0]);
+        let c01 = lerp(self.voxel(x0, y0, z1), self.voxel(x1, y0, z1), frac[0]);
+        let c11 = lerp(self.voxel(x0, y1, z1), self.voxel(x1, y1, z1), frac[0]);
+        lerp(lerp(c00, c10, frac[1]), lerp(c01, c11, frac[1]), frac[2])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid_bytes(resolution: [u32; 3], bounds: [f32; 6], values: &[f32]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for n in resolution {
+            bytes.extend_from_slice(&n.to_le_bytes());
+        }
+        for v in bounds.iter().chain(values) {
+            bytes.extend_from_slice(&v.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn loads_and_interpolates_between_voxel_centres() {
+        let bytes = grid_bytes([2, 1, 1], [0.0, 0.0, 0.0, 2.0, 1.0, 1.0], &[1.0, 3.0]);
+        let grid = VoxelGrid::from_bytes(&bytes).unwrap();
+        assert_eq!(grid.max_value(), 3.0);
+        assert_eq!(grid.value(&point::Point3::new(0.5, 0.5, 0.5)), 1.0);
+        assert_eq!(grid.value(&point::Point3::new(1.0, 0.5, 0.5)), 2.0);
+        assert_eq!(grid.value(&point::Point3::new(1.5, 0.5, 0.5)), 3.0);
+        assert_eq!(grid.value(&point::Point3::new(3.0, 0.5, 0.5)), 0.0);
+    }
+
+    #[test]
+    fn truncated_grids_are_rejected() {
+        let bytes = grid_bytes([2, 2, 2], [0.0, 0.0, 0.0, 1.0, 1.0, 1.0], &[1.0; 7]);
+        let error = VoxelGrid::from_bytes(&bytes).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+        assert!(VoxelGrid::from_bytes(&bytes[..10]).is_err());
+    }
+
+    #[test]
+    fn invalid_resolutions_are_rejected() {
+        let invalid_data = |resolution: [u32; 3]| {
+            let bytes = grid_bytes(resolution, [0.0, 0.0, 0.0, 1.0, 1.0, 1.0], &[]);
+            VoxelGrid::from_bytes(&bytes).err().unwrap().kind() == io::ErrorKind::InvalidData
+        };
+        assert!(invalid_data([0, 1, 1]));
+        // Would wrap to zero voxels if the count were allowed to overflow
+        assert!(invalid_data([1 << 22, 1 << 21, 1 << 21]));
+        assert!(invalid_data([u32::MAX, u32::MAX, u32::MAX]));
+
+        let min = point::Point3::new(0.0, 0.0, 0.0);
+        let max = point::Point3::new(1.0, 1.0, 1.0);
+        assert!(VoxelGrid::new((2, 1, 1), min, max, vec![1.0]).is_err());
+        assert!(VoxelGrid::new((usize::MAX, 2, 1), min, max, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        let invalid_data = |value: f32| {
+            let bytes = grid_bytes([2, 1, 1], [0.0, 0.0, 0.0, 1.0, 1.0, 1.0], &[1.0, value]);
+            VoxelGrid::from_bytes(&bytes).err().unwrap().kind() == io::ErrorKind::InvalidData
+        };
+        assert!(invalid_data(-0.5));
+        assert!(invalid_data(f32::NAN));
+        assert!(invalid_data(f32::INFINITY));
+        assert!(VoxelGrid::from_bytes(&grid_bytes(
+            [2, 1, 1],
+            [0.0, 0.0, 0.0, 1.0, 1.0, 1.0],
+            &[0.0, 0.0]
+        ))
+        .is_ok());
+    }
+
+    #[test]
+    fn degenerate_bounds_are_rejected() {
+        let invalid_data = |bounds: [f32; 6]| {
+            let bytes = grid_bytes([1, 1, 1], bounds, &[1.0]);
+            VoxelGrid::from_bytes(&bytes).err().unwrap().kind() == io::ErrorKind::InvalidData
+        };
+        // Empty along one axis
+        assert!(invalid_data([0.0, 0.0, 0.0, 1.0, 0.0, 1.0]));
+        // Inverted
+        assert!(invalid_data([0.0, 0.0, 2.0, 1.0, 1.0, 1.0]));
+        // Not finite
+        assert!(invalid_data([f32::NAN, 0.0, 0.0, 1.0, 1.0, 1.0]));
+        assert!(invalid_data([0.0, 0.0, 0.0, f32::INFINITY, 1.0, 1.0]));
+    }
+}